@@ -1,16 +1,26 @@
+pub mod cage;
+pub mod constraint;
+pub mod geometry;
 pub mod positions;
+pub mod restriction;
 
 use std::{
-    convert::TryInto,
     fmt::{Debug, Display},
     io::{self, Read},
     num::NonZeroU8,
-    u8,
+    sync::Arc,
 };
 
 use fnv::FnvHashSet;
 
-use self::positions::{House, HOUSES, N_CELLS};
+use crate::naive::find_possibles;
+
+use self::{
+    cage::{parse_cages, parse_cells},
+    constraint::parse_constraint,
+    geometry::{ChessMove, Geometry, BUTTERFLY, SAMURAI, SYMBOLS, TWIN},
+    restriction::Restriction,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Tile {
@@ -21,29 +31,131 @@ pub struct Tile {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Board {
-    pub(crate) data: [Tile; N_CELLS],
+    pub(crate) data: Vec<Tile>,
+    pub(crate) geometry: Arc<Geometry>,
 }
 
+/// Rows of the picture, with `_` for empty cells and `.` for the gaps between overlapping grids
 impl Display for Board {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for chunk in self.data.iter().as_slice().chunks_exact(9) {
-            for cell in chunk.iter() {
+        let (height, width) = self.geometry.picture_shape();
+        for row in 0..height {
+            for col in 0..width {
                 write!(
                     f,
                     "{},",
-                    match cell.value {
-                        Some(val) => val.to_string(),
-                        None => '_'.to_string(),
+                    match self.geometry.cell_at(row, col) {
+                        Some(i) => match self.data[i].value {
+                            Some(val) => self.geometry.symbol(val),
+                            None => '_',
+                        },
+                        None => '.',
                     }
                 )?;
             }
-            writeln!(f, "")?;
+            writeln!(f)?;
         }
 
         Ok(())
     }
 }
 
+/// Something wrong with the digits on a board. Houses are indices into the board's `Geometry::houses`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// Two cells of `house` hold the same `value`
+    Duplicate {
+        house: usize,
+        cells: [usize; 2],
+        value: NonZeroU8,
+    },
+    /// An empty cell has no candidates left; `houses` are the houses it is in
+    NoCandidates { cell: usize, houses: Vec<usize> },
+    /// Two cells of `cage` hold the same `value`
+    CageDuplicate {
+        cage: usize,
+        cells: [usize; 2],
+        value: NonZeroU8,
+    },
+    /// The digits in `cage` add up to `total`, and no digits in its empty cells can make up its sum
+    CageSum { cage: usize, total: usize },
+    /// Two cells a `rule` move apart hold the same `value`
+    ChessMove {
+        rule: ChessMove,
+        cells: [usize; 2],
+        value: NonZeroU8,
+    },
+    /// The cells of a variant constraint, given as an index into `Geometry::constraints`,
+    /// are all filled and break it
+    Broken { constraint: usize },
+    /// Two orthogonally adjacent cells hold consecutive digits on a non-consecutive board
+    Consecutive { cells: [usize; 2] },
+    /// A cell shaded even or odd holds a digit of the other kind
+    Parity { cell: usize, value: NonZeroU8 },
+}
+
+impl Problem {
+    /// The problem as text, naming cells and houses as laid out by `geometry`
+    pub fn describe(&self, geometry: &Geometry) -> String {
+        match self {
+            Problem::Duplicate {
+                house,
+                cells,
+                value,
+            } => format!(
+                "{}: {} and {} both hold {}",
+                geometry.house_name(*house),
+                geometry.cell_name(cells[0]),
+                geometry.cell_name(cells[1]),
+                geometry.symbol(*value)
+            ),
+            Problem::NoCandidates { cell, houses } => format!(
+                "{}: {} has no candidates left",
+                houses
+                    .iter()
+                    .map(|h| geometry.house_name(*h))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                geometry.cell_name(*cell)
+            ),
+            Problem::CageDuplicate { cage, cells, value } => format!(
+                "{}: {} and {} both hold {}",
+                geometry.cage_name(*cage),
+                geometry.cell_name(cells[0]),
+                geometry.cell_name(cells[1]),
+                geometry.symbol(*value)
+            ),
+            Problem::CageSum { cage, total } => format!(
+                "{}: the digits add up to {}, and the sum cannot be reached",
+                geometry.cage_name(*cage),
+                total
+            ),
+            Problem::ChessMove { rule, cells, value } => format!(
+                "{}: {} and {} both hold {}",
+                rule.name(),
+                geometry.cell_name(cells[0]),
+                geometry.cell_name(cells[1]),
+                geometry.symbol(*value)
+            ),
+            Problem::Broken { constraint } => format!(
+                "{} is broken",
+                geometry.constraints()[*constraint].describe(geometry)
+            ),
+            Problem::Consecutive { cells } => format!(
+                "non-consecutive: {} and {} hold consecutive digits",
+                geometry.cell_name(cells[0]),
+                geometry.cell_name(cells[1])
+            ),
+            Problem::Parity { cell, value } => format!(
+                "{} is shaded {}, but holds {}",
+                geometry.cell_name(*cell),
+                if value.get() % 2 == 0 { "odd" } else { "even" },
+                geometry.symbol(*value)
+            ),
+        }
+    }
+}
+
 pub struct AnnotatedBoard {
     pub id: String,
     pub src: String,
@@ -66,56 +178,258 @@ pub struct AnnotatedBoard {
 /// 8,3,1,5,6,2,7,4,9
 /// 9,6,5,3,4,7,1,8,2
 /// ```
+///
+/// Header fields after the status name the variant of the puzzle, as read by `parse_variant`,
+/// such as `P7,Kids,unsolved,3x2` for 6x6 with boxes three rows high.
 pub fn parse_board_list(reader: &mut impl Read) -> io::Result<Vec<AnnotatedBoard>> {
     let mut buf = String::new();
     reader.read_to_string(&mut buf)?;
 
-    Ok(buf
-        .split("\n\n")
+    buf.split("\n\n")
         .map(|s| s.trim())
         .filter(|section| !section.trim().is_empty())
         .map(|section| {
             let (header, board_str) = section.split_at(section.find('\n').unwrap());
             let header: Vec<&str> = header.split(",").collect();
-            AnnotatedBoard {
+            let board = parse_variant(board_str, &header[3.min(header.len())..])
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            Ok(AnnotatedBoard {
                 id: header[0].to_owned(),
                 src: header[1].to_owned(),
                 status: header[2].to_owned(),
-                board: Board::from_str(board_str).unwrap(),
-            }
+                board,
+            })
         })
-        .collect())
+        .collect()
+}
+
+/// Parse a board of the variant named by `tags`, which may be any of
+/// - a box shape as rows x columns, for boxes that are not the usual ones for the grid size
+/// - `jigsaw`, when the digits are followed by a region map, as read by `Board::from_jigsaw_str`
+/// - `latin`, for a Latin square of any size, with rows and columns but no boxes
+/// - `x`, for Sudoku-X, where both main diagonals are houses too
+/// - `killer`, when the digits are followed by cages, one per line, as read by `parse_cages`
+/// - `windoku` or `hyper`, for the four extra windows of Windoku
+/// - `disjoint`, where the cells at the same place in every box are a house too
+/// - `antiknight` or `antiking`, where a digit may not repeat a knight's or king's move apart
+/// - `nonconsecutive`, where orthogonally adjacent cells may not hold consecutive digits
+/// - `samurai`, `twin` or `butterfly`, for overlapping 9x9 grids, whose digits are written as a
+///   picture as read by `Board::from_picture_str`
+///
+/// Whatever the tags, lines such as `region: r1c1 r1c2 ...` after the digits add extra houses,
+/// `even: r1c1 r1c2 ...` and `odd: ...` shade cells that must hold even or odd digits,
+/// and lines such as `thermo: r1c1 r1c2 r1c3` or `sandwich: r0c3 15` add variant constraints
+/// and outside clues, as read by `parse_constraint`.
+fn parse_variant(board_str: &str, tags: &[&str]) -> Result<Board, String> {
+    // cage, region and constraint lines are the ones with a label, such as "15: r1c1 r1c2"
+    let (labelled, digit_lines): (Vec<&str>, Vec<&str>) =
+        board_str.lines().partition(|line| line.contains(':'));
+    let label = |line: &str| line.split(':').next().unwrap().trim().to_ascii_lowercase();
+    let (region_lines, labelled): (Vec<&str>, Vec<&str>) = labelled
+        .into_iter()
+        .partition(|line| label(line) == "region");
+    let (shading_lines, labelled): (Vec<&str>, Vec<&str>) = labelled
+        .into_iter()
+        .partition(|line| label(line) == "even" || label(line) == "odd");
+    let (cage_lines, constraint_lines): (Vec<&str>, Vec<&str>) = labelled
+        .into_iter()
+        .partition(|line| label(line).parse::<usize>().is_ok());
+    let board_str = &digit_lines.join("\n");
+    if !cage_lines.is_empty() && !tags.contains(&"killer") {
+        return Err("Cages given for a board that is not killer".to_string());
+    }
+
+    let grids = tags.iter().find_map(|tag| match *tag {
+        "samurai" => Some(&SAMURAI[..]),
+        "twin" => Some(&TWIN[..]),
+        "butterfly" => Some(&BUTTERFLY[..]),
+        _ => None,
+    });
+    let mut board = if tags.contains(&"jigsaw") {
+        Board::from_jigsaw_str(board_str)?
+    } else if tags.contains(&"latin") {
+        let nsymbols = board_str.chars().filter(|c| is_symbol(*c)).count();
+        let size = (1..=SYMBOLS.len())
+            .find(|n| n * n == nsymbols)
+            .ok_or_else(|| format!("Expected a square grid, got {} symbols", nsymbols))?;
+        Board::from_str_with(board_str, Arc::new(Geometry::latin(size)))?
+    } else if let Some(grids) = grids {
+        Board::from_picture_str(board_str, Arc::new(Geometry::overlapping(grids)?))?
+    } else {
+        match tags.iter().find_map(|tag| parse_box_shape(tag)) {
+            Some((rows, cols)) => {
+                Board::from_str_with(board_str, Arc::new(Geometry::rectangular(rows, cols)))?
+            }
+            None => Board::from_str(board_str)?,
+        }
+    };
+
+    for tag in tags {
+        match *tag {
+            "x" => board.geometry = Arc::new(board.geometry().clone().with_diagonals()),
+            "killer" => {
+                let cages = parse_cages(&cage_lines.join("\n"), board.geometry())?;
+                board.geometry = Arc::new(board.geometry().clone().with_cages(cages)?);
+            }
+            "windoku" | "hyper" => {
+                board.geometry = Arc::new(board.geometry().clone().with_windows()?)
+            }
+            "antiknight" | "anti-knight" => {
+                board.geometry =
+                    Arc::new(board.geometry().clone().with_chess_move(ChessMove::Knight))
+            }
+            "antiking" | "anti-king" => {
+                board.geometry = Arc::new(board.geometry().clone().with_chess_move(ChessMove::King))
+            }
+            "nonconsecutive" | "non-consecutive" => {
+                board.geometry = Arc::new(
+                    board
+                        .geometry()
+                        .clone()
+                        .with_restriction(Restriction::NonConsecutive)?,
+                )
+            }
+            "disjoint" | "disjoint-groups" => {
+                board.geometry = Arc::new(board.geometry().clone().with_disjoint_groups()?)
+            }
+            "jigsaw" | "latin" | "samurai" | "twin" | "butterfly" => {}
+            _ if parse_box_shape(tag).is_some() => {}
+            _ => return Err(format!("Unknown variant {}", tag)),
+        }
+    }
+
+    if !region_lines.is_empty() {
+        let regions = region_lines
+            .iter()
+            .map(|line| parse_cells(line.split_once(':').unwrap().1, board.geometry()))
+            .collect::<Result<Vec<_>, _>>()?;
+        board.geometry = Arc::new(board.geometry().clone().with_regions(regions)?);
+    }
+
+    let mut geometry = board.geometry().clone();
+    for line in shading_lines {
+        let (label, cells) = line.split_once(':').unwrap();
+        for cell in parse_cells(cells, &geometry)? {
+            let restriction = match label.trim().to_ascii_lowercase().as_str() {
+                "even" => Restriction::Even(cell),
+                _ => Restriction::Odd(cell),
+            };
+            geometry = geometry.with_restriction(restriction)?;
+        }
+    }
+    for line in constraint_lines {
+        let constraint = parse_constraint(line, &geometry)?;
+        geometry = geometry.with_constraint(constraint)?;
+    }
+    board.geometry = Arc::new(geometry);
+    Ok(board)
+}
+
+/// Box shape written as rows x columns, such as "2x3"
+fn parse_box_shape(shape: &str) -> Option<(usize, usize)> {
+    let (rows, cols) = shape.trim().split_once('x')?;
+    let (rows, cols) = (rows.parse().ok()?, cols.parse().ok()?);
+    if rows * cols <= SYMBOLS.len() && rows > 0 && cols > 0 {
+        Some((rows, cols))
+    } else {
+        None
+    }
 }
 
 impl Board {
-    /// Parse a Board from a &str representation
+    /// An empty board laid out by `geometry`
+    pub fn empty(geometry: Arc<Geometry>) -> Self {
+        Self {
+            data: vec![Tile { value: None }; geometry.n_cells()],
+            geometry,
+        }
+    }
+
+    /// Parse a Board from a &str representation.
+    ///
+    /// The size of the grid is worked out from the number of symbols, such as 81 for 9x9
+    /// or 36 for 6x6, and the boxes are laid out by `Geometry::for_size`.
+    /// Digits above 9 are written as letters.
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(repr: &str) -> Result<Self, String> {
-        Ok(Self {
-            data: repr
-                .chars()
-                .filter(|c| c.is_ascii_digit() || c == &'_')
-                .map(|c| match c.to_digit(10) {
-                    Some(num) => Tile {
-                        value: Some(NonZeroU8::new(num as u8).unwrap()),
-                        // possibles: FnvHashSet::with_capacity_and_hasher(0, Default::default()),
-                        // used_possibilities: false,
-                    },
-                    None => Tile {
-                        value: None,
-                        // possibles: FnvHashSet::with_hasher(Default::default()),
-                        // used_possibilities: false,
-                    },
-                })
-                .collect::<Vec<_>>()
-                .try_into()
-                .or_else(|e: Vec<_>| {
-                    Err(format!(
-                        "Expected {} numbers in board, got {}",
-                        N_CELLS,
-                        e.len()
-                    ))
-                })?,
-        })
+        let nsymbols = cell_tokens(repr).len();
+        let size = (1..=SYMBOLS.len()).find(|n| n * n == nsymbols);
+        match size.and_then(Geometry::for_size) {
+            Some(geometry) => Self::from_str_with(repr, Arc::new(geometry)),
+            None => Err(format!(
+                "Expected a sudoku grid such as 4x4, 6x6 or 9x9, got {} symbols",
+                nsymbols
+            )),
+        }
+    }
+
+    /// Parse a jigsaw Board from its digits followed by its region map, with one symbol
+    /// per cell in each. See `Geometry::from_region_map` for the region map.
+    pub fn from_jigsaw_str(repr: &str) -> Result<Self, String> {
+        let symbols: Vec<char> = repr.chars().filter(|c| is_symbol(*c)).collect();
+        let (digits, regions) = symbols.split_at(symbols.len() / 2);
+        let geometry = Geometry::from_region_map(&regions.iter().collect::<String>())?;
+        Self::from_str_with(&digits.iter().collect::<String>(), Arc::new(geometry))
+    }
+
+    /// Parse a Board of overlapping grids laid out by `geometry` from a picture of it: every row
+    /// of the picture in turn, with `.` for each gap between the grids, as `Display` writes it
+    pub fn from_picture_str(repr: &str, geometry: Arc<Geometry>) -> Result<Self, String> {
+        let spots: Vec<char> = repr
+            .chars()
+            .filter(|c| is_symbol(*c) || *c == '.')
+            .collect();
+        let (height, width) = geometry.picture_shape();
+        if spots.len() != height * width {
+            return Err(format!(
+                "Expected a picture of {} rows of {} cells and gaps, got {} in all",
+                height,
+                width,
+                spots.len()
+            ));
+        }
+        for (k, c) in spots.iter().enumerate() {
+            let (row, col) = (k / width, k % width);
+            let is_gap = geometry.cell_at(row, col).is_none();
+            if is_gap != (*c == '.') {
+                return Err(format!(
+                    "Expected {} at r{}c{} of the picture, got {}",
+                    if is_gap { "a gap" } else { "a cell" },
+                    row + 1,
+                    col + 1,
+                    c
+                ));
+            }
+        }
+        Self::from_str_with(&spots.iter().collect::<String>(), geometry)
+    }
+
+    /// Parse a Board laid out by `geometry` from a &str representation. When the cells are
+    /// separated, such as `10,_,16,...`, each one may be a number as well as a symbol
+    pub fn from_str_with(repr: &str, geometry: Arc<Geometry>) -> Result<Self, String> {
+        let data = cell_tokens(repr)
+            .iter()
+            .map(|token| match *token {
+                "_" => Ok(Tile { value: None }),
+                _ => match parse_value(token, &geometry) {
+                    Some(value) => Ok(Tile { value: Some(value) }),
+                    None => Err(format!("Unexpected symbol {} in board", token)),
+                },
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if data.len() != geometry.n_cells() {
+            return Err(format!(
+                "Expected {} numbers in board, got {}",
+                geometry.n_cells(),
+                data.len()
+            ));
+        }
+        Ok(Self { data, geometry })
+    }
+
+    /// Shape of the board: its size and houses
+    pub fn geometry(&self) -> &Geometry {
+        &self.geometry
     }
 
     /// Check if all spots on the board is filled
@@ -125,11 +439,80 @@ impl Board {
 
     /// Check if board is correctly solved
     pub fn is_solved(&self) -> bool {
-        HOUSES.iter().all(|rule| self.is_valid_on_house(rule))
+        self.geometry
+            .houses()
+            .iter()
+            .all(|rule| self.is_valid_on_house(rule))
+            && self
+                .geometry
+                .cages()
+                .iter()
+                .all(|cage| cage.is_solved(self))
+            && self.chess_move_clashes().is_empty()
+            && self.restriction_clashes().is_empty()
+            && self
+                .geometry
+                .constraints()
+                .iter()
+                .all(|constraint| constraint.is_satisfied(self))
+    }
+
+    /// Every pair of cells, lowest first, that hold the same digit a forbidden chess move apart
+    fn chess_move_clashes(&self) -> Vec<Problem> {
+        let mut problems = vec![];
+        for rule in self.geometry.chess_moves() {
+            for a in 0..self.data.len() {
+                if let Some(value) = self.data[a].value {
+                    for b in self.geometry.move_targets(a, *rule) {
+                        if b > a && self.data[b].value == Some(value) {
+                            problems.push(Problem::ChessMove {
+                                rule: *rule,
+                                cells: [a, b],
+                                value,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        problems
+    }
+
+    /// Every shaded cell holding a digit of the wrong kind, and every pair of adjacent cells,
+    /// lowest first, holding consecutive digits on a non-consecutive board
+    fn restriction_clashes(&self) -> Vec<Problem> {
+        let mut problems = vec![];
+        for restriction in self.geometry.restrictions() {
+            match restriction {
+                Restriction::NonConsecutive => {
+                    for a in 0..self.data.len() {
+                        if let Some(value) = self.data[a].value {
+                            for b in self.geometry.orthogonal_neighbors(a) {
+                                if b > a
+                                    && self.data[b]
+                                        .value
+                                        .is_some_and(|v| v.get().abs_diff(value.get()) == 1)
+                                {
+                                    problems.push(Problem::Consecutive { cells: [a, b] });
+                                }
+                            }
+                        }
+                    }
+                }
+                Restriction::Even(cell) | Restriction::Odd(cell) => {
+                    if let Some(value) = self.data[*cell].value {
+                        if !restriction.allows(self, *cell, value) {
+                            problems.push(Problem::Parity { cell: *cell, value });
+                        }
+                    }
+                }
+            }
+        }
+        problems
     }
 
     /// Checks that the board is valid against a certain `Rule`, such as for a row, with rule indeces [0, 1, 2, 3, 4, 5, 6, 7, 8]
-    pub fn is_valid_on_house(&self, rule: &House) -> bool {
+    pub fn is_valid_on_house(&self, rule: &[usize]) -> bool {
         if self.data.iter().any(|cell| cell.value.is_none()) {
             return false;
         }
@@ -139,12 +522,1009 @@ impl Board {
             .map(|pos| self.data[*pos].value.unwrap())
             .collect();
 
-        // should sum up to a constant; we assume that values are always in [1, 9]
-        nums.iter().map(|u| u8::from(*u)).fold(0, u8::wrapping_add) == 1 + 2 + 3 + 4 + 5 + 6 + 7 + 8 + 9 &&
+        // should sum up to a constant; we assume that values are always in [1, size]
+        let size = self.geometry.size();
+        nums.iter().map(|u| u.get() as usize).sum::<usize>() == size * (size + 1) / 2 &&
             // should not repeat
             {
-                let mut seen = FnvHashSet::with_capacity_and_hasher(9, Default::default());
+                let mut seen = FnvHashSet::with_capacity_and_hasher(size, Default::default());
                 nums.iter().all(|x| seen.insert(x))
             }
     }
+
+    /// Find every pair of cells that break a house, cage, chess rule or non-consecutive rule, every
+    /// shaded cell of the wrong parity, every cage that can no longer reach its sum, every filled-in variant constraint that is broken, and every empty cell
+    /// without candidates.
+    /// Empty if nothing is wrong, although the board may still have no solution.
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = vec![];
+        for (h, house) in self.geometry.houses().iter().enumerate() {
+            for (pos, a) in house.iter().enumerate() {
+                if let Some(value) = self.data[*a].value {
+                    for b in house[pos + 1..].iter() {
+                        if self.data[*b].value == Some(value) {
+                            problems.push(Problem::Duplicate {
+                                house: h,
+                                cells: [*a, *b],
+                                value,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        for (c, cage) in self.geometry.cages().iter().enumerate() {
+            for (pos, a) in cage.cells.iter().enumerate() {
+                if let Some(value) = self.data[*a].value {
+                    for b in cage.cells[pos + 1..].iter() {
+                        if self.data[*b].value == Some(value) {
+                            problems.push(Problem::CageDuplicate {
+                                cage: c,
+                                cells: [*a, *b],
+                                value,
+                            });
+                        }
+                    }
+                }
+            }
+            // a repeat is reported above, so only check the sum of a cage without one
+            let values: FnvHashSet<_> = cage
+                .cells
+                .iter()
+                .flat_map(|i| self.data[*i].value)
+                .collect();
+            let nfilled = cage
+                .cells
+                .iter()
+                .filter(|i| self.data[**i].value.is_some())
+                .count();
+            if values.len() == nfilled && cage.completions(self).is_empty() {
+                problems.push(Problem::CageSum {
+                    cage: c,
+                    total: values.iter().map(|v| v.get() as usize).sum(),
+                });
+            }
+        }
+
+        problems.extend(self.chess_move_clashes());
+        problems.extend(self.restriction_clashes());
+        for (c, constraint) in self.geometry.constraints().iter().enumerate() {
+            let filled = constraint
+                .cells()
+                .iter()
+                .all(|i| self.data[*i].value.is_some());
+            if filled && !constraint.is_satisfied(self) {
+                problems.push(Problem::Broken { constraint: c });
+            }
+        }
+
+        for i in 0..self.data.len() {
+            if self.data[i].value.is_none() && find_possibles(self, i).is_empty() {
+                problems.push(Problem::NoCandidates {
+                    cell: i,
+                    houses: self.geometry.houses_of(i).to_vec(),
+                });
+            }
+        }
+        problems
+    }
+}
+
+/// Whether a character in a board representation stands for a cell
+fn is_symbol(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// The cells written in a board representation. When every run of symbols between separators
+/// is one cell, as in `10,_,16`, those runs are the cells; otherwise every symbol is one
+fn cell_tokens(repr: &str) -> Vec<&str> {
+    let tokens: Vec<&str> = repr
+        .split(|c| !is_symbol(c))
+        .filter(|token| !token.is_empty())
+        .collect();
+    let is_cell =
+        |token: &&str| token.len() == 1 || token.parse::<usize>().is_ok_and(|n| n <= SYMBOLS.len());
+    if tokens.iter().all(is_cell) {
+        tokens
+    } else {
+        repr.matches(is_symbol).collect()
+    }
+}
+
+/// Digit written as `token`: either a single symbol or a number up to the size of the grid
+fn parse_value(token: &str, geometry: &Geometry) -> Option<NonZeroU8> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => geometry.value_of(c),
+        _ => token
+            .parse::<u8>()
+            .ok()
+            .filter(|n| (*n as usize) <= geometry.size())
+            .and_then(NonZeroU8::new),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroU8, sync::Arc};
+
+    use crate::{
+        logic::solve_path, naive::recursive_solve, smart::smart_solve, template::template_solve,
+    };
+
+    use super::{
+        geometry::{ChessMove, Geometry},
+        parse_board_list,
+        restriction::Restriction,
+        Board, Problem,
+    };
+
+    #[test]
+    fn test_validate() {
+        let b = Board::from_str(
+            "5,5,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            1,2,3,_,_,_,_,_,_
+            4,_,6,_,_,_,_,_,_
+            7,8,9,_,_,_,_,_,_",
+        )
+        .unwrap();
+        let five = NonZeroU8::new(5).unwrap();
+        assert_eq!(
+            vec![
+                Problem::Duplicate {
+                    house: 0,
+                    cells: [0, 1],
+                    value: five
+                },
+                Problem::Duplicate {
+                    house: 18,
+                    cells: [0, 1],
+                    value: five
+                },
+                // r8c2 sees 1-4 and 6-9 in its box, and the 5 above it in column 2
+                Problem::NoCandidates {
+                    cell: 64,
+                    houses: vec![7, 10, 24]
+                },
+            ],
+            b.validate()
+        );
+        assert_eq!(
+            "row 1: r1c1 and r1c2 both hold 5",
+            b.validate()[0].describe(b.geometry())
+        );
+    }
+
+    #[test]
+    fn test_validate_good_board() {
+        let b = Board::from_str(
+            "_,_,4,1,_,_,5,2,7
+            2,1,3,7,_,_,_,_,_
+            _,_,7,6,2,4,_,_,_
+            3,5,_,2,7,_,_,_,_
+            _,_,_,_,3,_,8,7,5
+            _,4,_,_,_,6,_,1,3
+            4,7,2,_,1,_,_,5,_
+            _,3,1,_,6,2,_,_,9
+            9,_,_,_,_,_,1,8,_",
+        )
+        .unwrap();
+        assert!(b.validate().is_empty());
+    }
+
+    #[test]
+    fn test_parse_other_sizes() {
+        let b = Board::from_str(
+            "1,_,_,4
+            _,4,1,_
+            2,_,_,3
+            _,3,2,_",
+        )
+        .unwrap();
+        assert_eq!(4, b.geometry().size());
+        assert_eq!(b, Board::from_str(&b.to_string()).unwrap());
+
+        // letters stand for digits above 9, in either case
+        let mut repr = "_,".repeat(254);
+        repr.push_str("g,A");
+        let b = Board::from_str(&repr).unwrap();
+        assert_eq!(16, b.geometry().size());
+        assert_eq!(Some(NonZeroU8::new(16).unwrap()), b.data[254].value);
+        assert!(b.to_string().ends_with("G,A,\n"));
+
+        // or numbers, when the cells are separated
+        let numbers = repr.replace("g,A", "16,10");
+        assert_eq!(b, Board::from_str(&numbers).unwrap());
+        let mut big = "_ ".repeat(624);
+        big.push_str("25");
+        let b = Board::from_str(&big).unwrap();
+        assert_eq!(25, b.geometry().size());
+        assert_eq!(Some(NonZeroU8::new(25).unwrap()), b.data[624].value);
+        // 17 is not a digit on a 16x16 board
+        assert!(Board::from_str(&numbers.replace("16", "17")).is_err());
+
+        assert!(Board::from_str("1,2,3").is_err());
+        // 7x7 has no boxes
+        assert!(Board::from_str(&"_".repeat(49)).is_err());
+        assert!(Board::from_str_with(&repr, Arc::new(Geometry::standard())).is_err());
+        // H is not a digit on a 16x16 board
+        assert!(Board::from_str(&repr.replace('g', "H")).is_err());
+    }
+
+    #[test]
+    fn test_parse_box_shape() {
+        let boards = parse_board_list(
+            &mut "K1,Kids,unsolved
+            1,_,_,_,_,_
+            _,_,1,_,_,_
+            _,_,_,_,_,_
+            _,_,_,_,_,_
+            _,_,_,_,_,_
+            _,_,_,_,_,6
+
+            K2,Kids,unsolved,3x2
+            1,_,_,_,_,_
+            _,_,1,_,_,_
+            _,_,_,_,_,_
+            _,_,_,_,_,_
+            _,_,_,_,_,_
+            _,_,_,_,_,6"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(Some((2, 3)), boards[0].board.geometry().box_shape());
+        assert_eq!(Some((3, 2)), boards[1].board.geometry().box_shape());
+        // the two 1s share a box when boxes are 3 wide, but not when they are 2 wide
+        assert!(!boards[0].board.validate().is_empty());
+        assert!(boards[1].board.validate().is_empty());
+
+        assert!(parse_board_list(&mut "K3,Kids,unsolved,3y2\n1,2,3,4".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_jigsaw() {
+        let boards = parse_board_list(
+            &mut "J1,Print,unsolved,jigsaw
+            _,9,1,_,6,_,5,_,3
+            5,_,4,_,9,2,_,7,_
+            8,6,_,4,_,5,_,1,9
+            _,5,_,3,2,_,1,_,8
+            1,_,9,_,5,_,4,3,_
+            4,_,3,9,_,1,_,6,_
+            _,1,_,8,_,9,6,_,4
+            _,4,5,_,1,_,9,_,7
+            9,_,8,_,4,6,_,2,_
+            AAAABBCCC
+            AAABBBCCC
+            AABBBBCCC
+            DDDEEEEFF
+            DDDEEEFFF
+            DDDEEFFFF
+            GGGHHHIII
+            GGGHHHIII
+            GGGHHHIII"
+                .as_bytes(),
+        )
+        .unwrap();
+        let b = &boards[0].board;
+        assert_eq!("region 1", b.geometry().house_name(18));
+        assert!(b.validate().is_empty());
+
+        let solved = recursive_solve(b.clone()).solved.unwrap();
+        assert!(solved.is_solved());
+        assert!(solve_path(b.clone()).is_solved());
+
+        // r1c4 and r3c2 are in different boxes, but the same region
+        let mut clash = Board::empty(b.geometry.clone());
+        let six = NonZeroU8::new(6).unwrap();
+        clash.data[3].value = Some(six);
+        clash.data[19].value = Some(six);
+        assert_eq!(
+            vec![Problem::Duplicate {
+                house: 18,
+                cells: [3, 19],
+                value: six
+            }],
+            clash.validate()
+        );
+    }
+
+    #[test]
+    fn test_parse_sudoku_x() {
+        let boards = parse_board_list(
+            &mut "X1,Subscription,unsolved,x
+            _,_,4,_,6,_,1,_,_
+            3,_,6,_,_,9,_,2,_
+            _,8,_,5,_,2,_,_,3
+            _,6,_,_,9,_,3,_,2
+            4,_,1,_,3,_,_,6,_
+            8,_,_,7,_,6,_,1,_
+            _,1,_,9,_,_,8,_,6
+            _,_,2,_,1,_,7,_,_
+            9,_,8,_,_,5,_,4,_"
+                .as_bytes(),
+        )
+        .unwrap();
+        let b = &boards[0].board;
+        assert_eq!(29, b.geometry().houses().len());
+        assert!(b.validate().is_empty());
+
+        let solved = recursive_solve(b.clone()).solved.unwrap();
+        assert!(solved.is_solved());
+        assert!(b
+            .geometry()
+            .house(27)
+            .iter()
+            .all(|i| solved.data[*i].value.is_some()));
+        assert!(solve_path(b.clone()).is_solved());
+
+        // a second 3 on the main diagonal is fine on a plain board
+        let mut clash = b.clone();
+        let three = NonZeroU8::new(3).unwrap();
+        clash.data[70].value = Some(three);
+        assert_eq!(
+            Problem::Duplicate {
+                house: 27,
+                cells: [40, 70],
+                value: three
+            },
+            clash.validate()[0]
+        );
+        assert!(Board::from_str(&clash.to_string())
+            .unwrap()
+            .validate()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_killer() {
+        let killer = "K1,Web,unsolved,killer
+            _,_,_,_
+            _,_,_,_
+            _,_,_,_
+            _,_,_,_
+            4: r1c1 r2c1
+            5: r1c2 r1c3
+            6: r1c4 r2c4
+            9: r2c2 r2c3 r3c3
+            9: r3c1 r4c1 r4c2
+            1: r3c2
+            4: r3c4 r4c4
+            2: r4c3";
+        let boards = parse_board_list(&mut killer.as_bytes()).unwrap();
+        let b = &boards[0].board;
+        assert_eq!(8, b.geometry().cages().len());
+        assert_eq!(Some(3), b.geometry().cage_of(10));
+        assert!(b.validate().is_empty());
+
+        let solution = Board::from_str("3,4,1,2,1,2,3,4,2,1,4,3,4,3,2,1").unwrap();
+        assert_eq!(
+            solution.data,
+            recursive_solve(b.clone()).solved.unwrap().data
+        );
+        assert_eq!(solution.data, smart_solve(b.clone()).solved.unwrap().data);
+        assert_eq!(
+            solution.data,
+            template_solve(b.clone()).solved.unwrap().data
+        );
+        // the same digits are no solution once the cages are gone
+        assert!(!b.is_solved());
+        assert!(solution.is_solved());
+
+        let mut clash = b.clone();
+        let (two, three, four) = (
+            NonZeroU8::new(2).unwrap(),
+            NonZeroU8::new(3).unwrap(),
+            NonZeroU8::new(4).unwrap(),
+        );
+        clash.data[1].value = Some(four);
+        clash.data[2].value = Some(two);
+        clash.data[5].value = Some(three);
+        clash.data[10].value = Some(three);
+        assert_eq!(
+            vec![
+                Problem::CageSum { cage: 1, total: 6 },
+                Problem::CageDuplicate {
+                    cage: 3,
+                    cells: [5, 10],
+                    value: three
+                },
+            ],
+            clash
+                .validate()
+                .into_iter()
+                .filter(|p| !matches!(p, Problem::NoCandidates { .. }))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "cage 2 (5): the digits add up to 6, and the sum cannot be reached",
+            clash.validate()[0].describe(clash.geometry())
+        );
+
+        let overlapping = killer.replace("2: r4c3", "2: r4c3 r4c4");
+        assert!(parse_board_list(&mut overlapping.as_bytes()).is_err());
+        let untagged = killer.replace(",killer", "");
+        assert!(parse_board_list(&mut untagged.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_windoku() {
+        let windoku = "W1,Web,unsolved,windoku
+            _,5,_,_,_,7,_,9,8
+            _,_,_,_,8,9,4,_,_
+            _,8,_,2,_,_,_,3,_
+            7,_,_,_,2,_,_,_,_
+            9,2,_,_,_,_,_,6,_
+            6,_,_,_,5,_,_,_,_
+            _,6,_,_,_,2,9,8,_
+            8,_,2,_,9,_,_,5,_
+            _,9,_,6,_,_,_,4,_";
+        let boards = parse_board_list(&mut windoku.as_bytes()).unwrap();
+        let b = &boards[0].board;
+        assert_eq!(31, b.geometry().houses().len());
+        assert!(b.validate().is_empty());
+
+        let solution = Board::from_str(
+            "2,5,4,3,6,7,1,9,8
+            3,7,6,1,8,9,4,2,5
+            1,8,9,2,4,5,7,3,6
+            7,3,5,4,2,6,8,1,9
+            9,2,1,8,7,3,5,6,4
+            6,4,8,9,5,1,3,7,2
+            4,6,3,5,1,2,9,8,7
+            8,1,2,7,9,4,6,5,3
+            5,9,7,6,3,8,2,4,1",
+        )
+        .unwrap();
+        for solved in [
+            recursive_solve(b.clone()).solved.unwrap(),
+            smart_solve(b.clone()).solved.unwrap(),
+            template_solve(b.clone()).solved.unwrap(),
+            solve_path(b.clone()).end.into_board(),
+        ] {
+            assert_eq!(solution.data, solved.data);
+            assert!(b
+                .geometry()
+                .houses()
+                .iter()
+                .all(|house| solved.is_valid_on_house(house)));
+        }
+
+        // r3c2 and r4c4 share the first window, which is no house on a plain board
+        let mut clash = b.clone();
+        clash.data[30].value = NonZeroU8::new(8);
+        assert_eq!(
+            Problem::Duplicate {
+                house: 27,
+                cells: [19, 30],
+                value: NonZeroU8::new(8).unwrap()
+            },
+            clash.validate()[0]
+        );
+        assert!(Board::from_str(&clash.to_string())
+            .unwrap()
+            .validate()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_extra_regions() {
+        let extra = "E1,Web,unsolved
+            1,_,_,_
+            _,_,_,_
+            _,_,_,_
+            _,_,_,1
+            region: r1c1 r2c2 r3c3 r4c4";
+        let boards = parse_board_list(&mut extra.as_bytes()).unwrap();
+        let b = &boards[0].board;
+        assert_eq!(13, b.geometry().houses().len());
+        assert_eq!(
+            "extra region 1: r1c1 and r4c4 both hold 1",
+            b.validate()[0].describe(b.geometry())
+        );
+
+        let short = extra.replace(" r4c4", "");
+        assert!(parse_board_list(&mut short.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_chess_moves() {
+        let anti_knight = "N1,Competition,unsolved,antiknight
+            6,_,_,_,_,_,_,4,_
+            _,_,_,_,_,_,_,9,_
+            _,_,_,3,9,_,_,6,5
+            _,_,_,_,3,_,_,7,_
+            _,_,_,1,_,_,_,_,_
+            3,9,_,_,4,2,_,5,_
+            9,_,6,_,2,_,5,8,_
+            5,7,4,8,_,_,3,2,_
+            _,2,_,5,7,9,_,_,_";
+        let knight_solution = "6,3,9,2,5,1,7,4,8
+            4,5,2,6,8,7,1,9,3
+            1,8,7,3,9,4,2,6,5
+            2,6,8,9,3,5,4,7,1
+            7,4,5,1,6,8,9,3,2
+            3,9,1,7,4,2,8,5,6
+            9,1,6,4,2,3,5,8,7
+            5,7,4,8,1,6,3,2,9
+            8,2,3,5,7,9,6,1,4";
+        let anti_king = "G1,Competition,unsolved,antiking
+            _,9,1,8,2,_,_,6,3
+            _,2,_,_,5,1,_,8,9
+            _,_,_,_,9,3,_,_,7
+            _,_,_,_,1,_,_,9,_
+            _,_,_,_,_,_,_,_,_
+            7,1,_,_,_,_,_,4,_
+            9,_,_,_,4,_,_,7,6
+            6,_,4,9,_,_,1,5,_
+            _,_,5,_,8,_,_,_,_";
+        let king_solution = "4,9,1,8,2,7,5,6,3
+            3,2,7,6,5,1,4,8,9
+            5,6,8,4,9,3,2,1,7
+            2,4,3,7,1,8,6,9,5
+            8,5,9,2,6,4,7,3,1
+            7,1,6,5,3,9,8,4,2
+            9,8,2,1,4,5,3,7,6
+            6,3,4,9,7,2,1,5,8
+            1,7,5,3,8,6,9,2,4";
+
+        for (puzzle, solution, rule) in [
+            (anti_knight, knight_solution, ChessMove::Knight),
+            (anti_king, king_solution, ChessMove::King),
+        ] {
+            let b = &parse_board_list(&mut puzzle.as_bytes()).unwrap()[0].board;
+            assert_eq!(&[rule], b.geometry().chess_moves());
+            assert!(b.validate().is_empty());
+
+            let solution = Board::from_str(solution).unwrap();
+            assert_eq!(
+                solution.data,
+                recursive_solve(b.clone()).solved.unwrap().data
+            );
+            assert_eq!(solution.data, smart_solve(b.clone()).solved.unwrap().data);
+            assert_eq!(
+                solution.data,
+                template_solve(b.clone()).solved.unwrap().data
+            );
+        }
+
+        // r2c2 and r3c4 are a knight's move apart
+        let mut clash = parse_board_list(&mut anti_knight.as_bytes()).unwrap()[0]
+            .board
+            .clone();
+        clash.data[10].value = NonZeroU8::new(3);
+        let problems = clash.validate();
+        assert_eq!(
+            Problem::ChessMove {
+                rule: ChessMove::Knight,
+                cells: [10, 21],
+                value: NonZeroU8::new(3).unwrap()
+            },
+            problems[0]
+        );
+        assert_eq!(
+            "anti-knight: r2c2 and r3c4 both hold 3",
+            problems[0].describe(clash.geometry())
+        );
+
+        let mut filled = Board::from_str(knight_solution).unwrap();
+        assert!(filled.is_solved());
+        filled.geometry = Arc::new(Geometry::standard().with_chess_move(ChessMove::King));
+        assert!(!filled.is_solved());
+    }
+
+    #[test]
+    fn test_parse_constraints() {
+        let clued = "C1,Web,unsolved
+            _,_,_,_
+            _,_,_,_
+            _,_,_,_
+            _,_,_,_
+            thermo: r3c2 r2c2 r1c2
+            arrow: r1c1 r2c1 r3c1
+            greater: r1c4 r1c3
+            black: r4c3 r4c4
+            v: r2c2 r2c3
+            white: r4c2 r4c3";
+        let b = &parse_board_list(&mut clued.as_bytes()).unwrap()[0].board;
+        assert_eq!(6, b.geometry().constraints().len());
+        assert_eq!(&[0, 4], b.geometry().constraints_of(5));
+        assert!(b.validate().is_empty());
+
+        let solution = Board::from_str("3,4,1,2,1,2,3,4,2,1,4,3,4,3,2,1").unwrap();
+        let solved = recursive_solve(b.clone()).solved.unwrap();
+        assert_eq!(solution.data, solved.data);
+        assert_eq!(solution.data, smart_solve(b.clone()).solved.unwrap().data);
+        assert_eq!(
+            solution.data,
+            template_solve(b.clone()).solved.unwrap().data
+        );
+        assert!(solved.is_solved());
+
+        // swapping the first row keeps the houses but breaks the greater-than sign
+        let mut broken = solved.clone();
+        broken.data[2].value = NonZeroU8::new(2);
+        broken.data[3].value = NonZeroU8::new(1);
+        assert!(!broken.is_solved());
+        let problems = broken.validate();
+        assert!(problems.contains(&Problem::Broken { constraint: 2 }));
+        assert!(problems
+            .iter()
+            .any(|p| p.describe(broken.geometry()) == "r1c4 > r1c3 is broken"));
+
+        let unknown = clued.replace("white:", "grey:");
+        assert!(parse_board_list(&mut unknown.as_bytes()).is_err());
+        let apart = clued.replace("v: r2c2 r2c3", "v: r2c2 r3c3");
+        assert!(parse_board_list(&mut apart.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        let lines = "L1,Web,unsolved
+            3,4,_,_
+            _,_,_,_
+            _,_,_,_
+            _,_,_,_
+            palindrome: r3c3 r3c4 r2c4
+            renban: r4c1 r4c2 r4c3
+            renban: r4c3 r3c3 r3c2 r4c2";
+        let b = &parse_board_list(&mut lines.as_bytes()).unwrap()[0].board;
+        assert!(b.validate().is_empty());
+
+        let solution = Board::from_str("3,4,1,2,1,2,3,4,2,1,4,3,4,3,2,1").unwrap();
+        assert_eq!(
+            solution.data,
+            recursive_solve(b.clone()).solved.unwrap().data
+        );
+        assert_eq!(solution.data, smart_solve(b.clone()).solved.unwrap().data);
+        assert_eq!(
+            solution.data,
+            template_solve(b.clone()).solved.unwrap().data
+        );
+    }
+
+    #[test]
+    fn test_parse_samurai() {
+        let samurai = "S1,Print,unsolved,samurai
+            4,9,1,_,_,7,_,6,_,.,.,.,_,_,_,_,_,5,_,_,6
+            7,2,8,_,6,_,_,_,9,.,.,.,4,_,_,8,9,1,2,_,_
+            _,5,_,1,9,_,8,_,7,.,.,.,5,_,1,_,_,3,9,_,_
+            _,_,_,5,_,_,_,_,_,.,.,.,_,_,_,9,_,4,8,_,1
+            _,_,5,_,8,_,3,1,6,.,.,.,8,4,9,_,7,_,_,3,2
+            9,6,_,_,1,_,_,5,_,.,.,.,_,_,5,3,_,2,_,_,4
+            _,8,4,2,7,_,_,_,_,_,4,7,_,6,8,5,_,_,_,_,9
+            _,7,_,9,_,_,_,8,4,3,5,_,_,9,2,6,_,_,_,4,5
+            5,_,9,_,4,8,_,7,_,8,6,9,_,5,_,_,1,9,_,_,7
+            .,.,.,.,.,.,4,_,_,_,9,2,8,_,6,.,.,.,.,.,.
+            .,.,.,.,.,.,7,_,9,1,8,6,5,4,_,.,.,.,.,.,.
+            .,.,.,.,.,.,_,6,8,4,3,5,9,_,_,.,.,.,.,.,.
+            _,_,8,6,_,_,5,_,_,6,_,_,_,_,_,_,_,_,_,_,_
+            6,4,_,3,_,9,_,1,_,_,_,_,_,_,_,8,9,7,2,_,1
+            1,_,2,_,_,_,3,9,6,_,_,_,_,_,1,6,_,2,3,_,8
+            _,_,1,5,3,2,9,_,_,.,.,.,_,9,2,_,_,5,_,_,6
+            _,7,_,_,1,_,6,5,3,.,.,.,7,1,_,9,_,_,_,_,_
+            4,3,_,_,_,6,_,_,8,.,.,.,_,_,_,3,_,_,9,_,4
+            _,_,4,_,_,3,2,_,9,.,.,.,_,4,_,_,7,_,6,_,_
+            9,_,_,_,6,4,7,8,_,.,.,.,5,2,7,4,_,_,_,_,_
+            _,_,_,_,8,5,4,3,_,.,.,.,_,_,_,_,_,9,_,_,_";
+        let solution = "4,9,1,8,2,7,5,6,3,.,.,.,9,8,3,7,2,5,4,1,6
+            7,2,8,3,6,5,1,4,9,.,.,.,4,7,6,8,9,1,2,5,3
+            3,5,6,1,9,4,8,2,7,.,.,.,5,2,1,4,6,3,9,7,8
+            8,1,7,5,3,6,4,9,2,.,.,.,2,3,7,9,5,4,8,6,1
+            2,4,5,7,8,9,3,1,6,.,.,.,8,4,9,1,7,6,5,3,2
+            9,6,3,4,1,2,7,5,8,.,.,.,6,1,5,3,8,2,7,9,4
+            6,8,4,2,7,1,9,3,5,2,4,7,1,6,8,5,4,7,3,2,9
+            1,7,2,9,5,3,6,8,4,3,5,1,7,9,2,6,3,8,1,4,5
+            5,3,9,6,4,8,2,7,1,8,6,9,3,5,4,2,1,9,6,8,7
+            .,.,.,.,.,.,4,5,3,7,9,2,8,1,6,.,.,.,.,.,.
+            .,.,.,.,.,.,7,2,9,1,8,6,5,4,3,.,.,.,.,.,.
+            .,.,.,.,.,.,1,6,8,4,3,5,9,2,7,.,.,.,.,.,.
+            3,9,8,6,2,1,5,4,7,6,1,3,2,8,9,1,3,4,7,6,5
+            6,4,7,3,5,9,8,1,2,9,7,4,6,3,5,8,9,7,2,4,1
+            1,5,2,8,4,7,3,9,6,5,2,8,4,7,1,6,5,2,3,9,8
+            8,6,1,5,3,2,9,7,4,.,.,.,3,9,2,7,4,5,1,8,6
+            2,7,9,4,1,8,6,5,3,.,.,.,7,1,4,9,6,8,5,2,3
+            4,3,5,7,9,6,1,2,8,.,.,.,8,5,6,3,2,1,9,7,4
+            5,8,4,1,7,3,2,6,9,.,.,.,9,4,8,5,7,3,6,1,2
+            9,1,3,2,6,4,7,8,5,.,.,.,5,2,7,4,1,6,8,3,9
+            7,2,6,9,8,5,4,3,1,.,.,.,1,6,3,2,8,9,4,5,7";
+        let b = &parse_board_list(&mut samurai.as_bytes()).unwrap()[0].board;
+        assert_eq!(369, b.data.len());
+        assert!(b.validate().is_empty());
+        // printing writes the picture back, gaps and all
+        assert_eq!(
+            b.data,
+            Board::from_picture_str(&b.to_string(), b.geometry.clone())
+                .unwrap()
+                .data
+        );
+        assert_eq!(21, b.to_string().lines().count());
+
+        let solution = Board::from_picture_str(solution, b.geometry.clone()).unwrap();
+        assert!(solution.is_solved());
+        for solved in [
+            recursive_solve(b.clone()).solved.unwrap(),
+            smart_solve(b.clone()).solved.unwrap(),
+            template_solve(b.clone()).solved.unwrap(),
+            solve_path(b.clone()).end.into_board(),
+        ] {
+            assert_eq!(solution.data, solved.data);
+        }
+
+        // r11c7 and r12c7 are only in the middle grid, whose first column is house 63
+        let mut clash = b.clone();
+        let (given, empty) = (
+            b.geometry().cell_at(10, 6).unwrap(),
+            b.geometry().cell_at(11, 6).unwrap(),
+        );
+        clash.data[empty].value = clash.data[given].value;
+        assert_eq!(
+            "column 19: r11c7 and r12c7 both hold 7",
+            clash.validate()[0].describe(clash.geometry())
+        );
+
+        let misplaced = samurai.replacen("_,.", ".,_", 1);
+        assert!(parse_board_list(&mut misplaced.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_non_consecutive() {
+        let non_consecutive = "NC1,Web,unsolved,nonconsecutive
+            _,_,_,_,8,_,_,2,6
+            _,_,3,7,2,6,_,_,_
+            _,_,_,_,_,9,4,_,_
+            _,9,_,8,3,_,_,_,_
+            8,_,7,_,_,_,_,_,4
+            2,_,_,_,_,_,8,_,_
+            _,_,8,_,_,_,6,1,_
+            _,_,_,_,_,_,_,4,_
+            _,_,_,_,_,_,_,_,_";
+        let b = &parse_board_list(&mut non_consecutive.as_bytes()).unwrap()[0].board;
+        assert_eq!(&[Restriction::NonConsecutive], b.geometry().restrictions());
+        assert!(b.validate().is_empty());
+
+        let solution = Board::from_str(
+            "1,5,9,4,8,3,7,2,6
+            4,8,3,7,2,6,1,5,9
+            7,2,6,1,5,9,4,8,3
+            5,9,4,8,3,7,2,6,1
+            8,3,7,2,6,1,5,9,4
+            2,6,1,5,9,4,8,3,7
+            9,4,8,3,7,2,6,1,5
+            3,7,2,6,1,5,9,4,8
+            6,1,5,9,4,8,3,7,2",
+        )
+        .unwrap();
+        for solved in [
+            recursive_solve(b.clone()).solved.unwrap(),
+            smart_solve(b.clone()).solved.unwrap(),
+            template_solve(b.clone()).solved.unwrap(),
+        ] {
+            assert_eq!(solution.data, solved.data);
+            assert!(solved.is_solved());
+        }
+
+        // a 6 in r5c2 fits its row, column and box, but is next to the 7 in r5c3
+        let mut clash = b.clone();
+        clash.data[37].value = NonZeroU8::new(6);
+        assert_eq!(
+            Problem::Consecutive { cells: [37, 38] },
+            clash.validate()[0]
+        );
+        assert_eq!(
+            "non-consecutive: r5c2 and r5c3 hold consecutive digits",
+            clash.validate()[0].describe(clash.geometry())
+        );
+    }
+
+    #[test]
+    fn test_parse_even_odd() {
+        let even_odd = "EO1,Web,unsolved
+            _,5,_,_,_,_,_,_,_
+            _,7,_,_,_,_,_,_,_
+            _,_,_,_,2,4,_,3,_
+            _,_,_,_,_,_,3,9,7
+            8,1,_,_,9,_,_,_,2
+            _,_,9,_,_,_,6,_,_
+            _,6,_,_,1,9,_,2,_
+            _,3,_,_,_,_,9,8,_
+            1,_,_,_,_,3,_,6,4
+            even: r2c9 r3c2 r4c5 r6c4 r6c7 r6c9 r7c2 r8c8 r9c8
+            odd: r2c2 r3c4 r4c3 r4c9 r6c1 r6c6 r7c4 r8c1 r8c9";
+        let b = &parse_board_list(&mut even_odd.as_bytes()).unwrap()[0].board;
+        assert_eq!(18, b.geometry().restrictions().len());
+        assert!(b.validate().is_empty());
+
+        let solution = Board::from_str(
+            "2,5,4,3,6,1,8,7,9
+            3,7,1,9,5,8,2,4,6
+            9,8,6,7,2,4,1,3,5
+            6,4,5,1,8,2,3,9,7
+            8,1,3,6,9,7,4,5,2
+            7,2,9,4,3,5,6,1,8
+            4,6,8,5,1,9,7,2,3
+            5,3,7,2,4,6,9,8,1
+            1,9,2,8,7,3,5,6,4",
+        )
+        .unwrap();
+        for solved in [
+            recursive_solve(b.clone()).solved.unwrap(),
+            smart_solve(b.clone()).solved.unwrap(),
+            template_solve(b.clone()).solved.unwrap(),
+        ] {
+            assert_eq!(solution.data, solved.data);
+        }
+
+        let mut clash = b.clone();
+        clash.data[17].value = NonZeroU8::new(5);
+        assert_eq!(
+            vec![Problem::Parity {
+                cell: 17,
+                value: NonZeroU8::new(5).unwrap()
+            }],
+            clash.validate()
+        );
+        assert_eq!(
+            "r2c9 is shaded even, but holds 5",
+            clash.validate()[0].describe(clash.geometry())
+        );
+
+        let both = even_odd.replace("odd: r2c2", "odd: r2c9");
+        assert!(parse_board_list(&mut both.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_outside_clues() {
+        let sandwich = "S1,Web,unsolved
+            _,8,_,_,9,5,4,1,_
+            _,_,1,8,_,_,_,5,_
+            _,9,_,_,_,_,_,6,2
+            _,7,_,_,_,_,_,_,_
+            _,_,_,_,7,_,_,_,_
+            3,_,9,6,_,_,_,_,_
+            1,_,8,4,_,_,7,_,5
+            _,6,4,7,_,_,_,_,1
+            _,_,_,_,1,_,_,_,_
+            sandwich: r1c0 9
+            sandwich: r2c0 29
+            sandwich: r3c0 12
+            sandwich: r4c0 7
+            sandwich: r5c0 0
+            sandwich: r6c10 0
+            sandwich: r7c10 30
+            sandwich: r8c10 35
+            sandwich: r9c10 0
+            sandwich: r0c1 0
+            sandwich: r0c2 11
+            sandwich: r0c3 13
+            sandwich: r0c4 22
+            sandwich: r0c5 35
+            sandwich: r10c6 4
+            sandwich: r10c7 0
+            sandwich: r10c8 28
+            sandwich: r10c9 20
+            little killer: r0c3 se 24
+            little killer: r10c1 ne 54";
+        let b = &parse_board_list(&mut sandwich.as_bytes()).unwrap()[0].board;
+        assert_eq!(20, b.geometry().constraints().len());
+        assert!(b.validate().is_empty());
+
+        let solution = Board::from_str(
+            "6,8,3,2,9,5,4,1,7
+            4,2,1,8,6,7,3,5,9
+            7,9,5,3,4,1,8,6,2
+            5,7,2,1,3,4,9,8,6
+            8,4,6,5,7,9,1,2,3
+            3,1,9,6,8,2,5,7,4
+            1,3,8,4,2,6,7,9,5
+            9,6,4,7,5,8,2,3,1
+            2,5,7,9,1,3,6,4,8",
+        )
+        .unwrap();
+        for solved in [
+            recursive_solve(b.clone()).solved.unwrap(),
+            smart_solve(b.clone()).solved.unwrap(),
+            template_solve(b.clone()).solved.unwrap(),
+        ] {
+            assert_eq!(solution.data, solved.data);
+        }
+
+        // swapping the first two rows keeps every house, but not the sandwiches of those rows
+        let mut swapped = b.clone();
+        swapped.data = solution.data.clone();
+        for col in 0..9 {
+            swapped.data.swap(col, 9 + col);
+        }
+        let problems = swapped.validate();
+        assert!(problems.contains(&Problem::Broken { constraint: 0 }));
+        assert!(problems.contains(&Problem::Broken { constraint: 1 }));
+        assert!(!problems.contains(&Problem::Broken { constraint: 2 }));
+        assert_eq!(
+            "sandwich of 9 on r1c1 to r1c9",
+            b.geometry().constraints()[0].describe(b.geometry())
+        );
+    }
+
+    #[test]
+    fn test_parse_latin() {
+        let latin = "L1,Web,unsolved,latin
+            _,3,4,_,_
+            5,_,_,_,1
+            _,_,_,_,_
+            2,_,5,_,_
+            _,_,_,4,_";
+        let b = &parse_board_list(&mut latin.as_bytes()).unwrap()[0].board;
+        assert_eq!(10, b.geometry().houses().len());
+        let solution = Board::from_str_with(
+            "1,3,4,5,2
+            5,4,3,2,1
+            4,5,2,1,3
+            2,1,5,3,4
+            3,2,1,4,5",
+            b.geometry.clone(),
+        )
+        .unwrap();
+        for solved in [
+            recursive_solve(b.clone()).solved.unwrap(),
+            smart_solve(b.clone()).solved.unwrap(),
+            template_solve(b.clone()).solved.unwrap(),
+        ] {
+            assert_eq!(solution.data, solved.data);
+        }
+        assert!(parse_board_list(&mut "L2,Web,unsolved,latin\n1,2,_".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_disjoint_groups() {
+        let disjoint = "D1,Web,unsolved,disjoint
+            _,_,_,_,3,8,6,7,_
+            _,_,9,_,_,_,_,_,_
+            4,_,_,_,9,_,_,_,1
+            _,_,_,_,_,_,_,_,_
+            _,_,6,5,_,_,9,_,_
+            _,5,_,8,1,_,_,_,4
+            7,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,4,_,_
+            _,_,2,_,4,_,_,_,_";
+        let b = &parse_board_list(&mut disjoint.as_bytes()).unwrap()[0].board;
+        assert_eq!(36, b.geometry().houses().len());
+        let solution = Board::from_str(
+            "2,1,5,4,3,8,6,7,9
+            3,6,9,2,7,1,8,4,5
+            4,7,8,6,9,5,3,2,1
+            8,2,4,9,6,7,1,5,3
+            1,3,6,5,2,4,9,8,7
+            9,5,7,8,1,3,2,6,4
+            7,4,1,3,8,6,5,9,2
+            6,9,3,7,5,2,4,1,8
+            5,8,2,1,4,9,7,3,6",
+        )
+        .unwrap();
+        for solved in [
+            recursive_solve(b.clone()).solved.unwrap(),
+            smart_solve(b.clone()).solved.unwrap(),
+            template_solve(b.clone()).solved.unwrap(),
+        ] {
+            assert_eq!(solution.data, solved.data);
+        }
+
+        // a 3 in r4c2 fits its row, column and box, but not the second cells of the boxes
+        let mut clash = b.clone();
+        clash.data[28].value = NonZeroU8::new(3);
+        assert_eq!(
+            vec![Problem::Duplicate {
+                house: 28,
+                cells: [4, 28],
+                value: NonZeroU8::new(3).unwrap()
+            }],
+            clash.validate()
+        );
+        assert_eq!("disjoint group 2", clash.geometry().house_name(28));
+    }
 }
@@ -0,0 +1,230 @@
+use super::{geometry::Geometry, Board};
+
+/// A killer cage: cells whose digits add up to `sum`, with no digit repeated among them.
+///
+/// Digit sets are passed around as bitmasks, with bit `d` standing for digit `d`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cage {
+    pub sum: usize,
+    pub cells: Vec<usize>,
+}
+
+impl Cage {
+    /// Sets of digits that can fill the empty cells of the cage on `board`: as many different
+    /// digits as there are empty cells, none already in the cage, making up the rest of the sum.
+    /// Empty if the digits already in the cage repeat or overshoot the sum.
+    pub fn completions(&self, board: &Board) -> Vec<u32> {
+        let (mut placed, mut total, mut nempty) = (0u32, 0, 0);
+        for i in self.cells.iter() {
+            match board.data[*i].value {
+                Some(v) => {
+                    if placed & 1 << v.get() != 0 {
+                        return vec![];
+                    }
+                    placed |= 1 << v.get();
+                    total += v.get() as usize;
+                }
+                None => nempty += 1,
+            }
+        }
+        if total > self.sum {
+            return vec![];
+        }
+
+        combinations(board.geometry.size(), nempty, self.sum - total)
+            .into_iter()
+            .filter(|combo| combo & placed == 0)
+            .collect()
+    }
+
+    /// Digits that can go in any empty cell of the cage on `board`
+    pub fn open_digits(&self, board: &Board) -> u32 {
+        self.completions(board)
+            .iter()
+            .fold(0, |acc, combo| acc | combo)
+    }
+
+    /// Whether the cage is filled with different digits adding up to its sum
+    pub fn is_solved(&self, board: &Board) -> bool {
+        self.cells.iter().all(|i| board.data[*i].value.is_some())
+            && !self.completions(board).is_empty()
+    }
+
+    /// Narrow down the candidates of the empty cells of the cage, given in cell order as
+    /// `masks`, to the digits that fit a completion in which every empty cell gets one of
+    /// its candidates. `None` if no completion fits.
+    pub(crate) fn fitting_candidates(&self, board: &Board, masks: &[u32]) -> Option<Vec<u32>> {
+        fitting_digits(masks, self.completions(board))
+    }
+}
+
+/// Sets of `count` different digits from 1 to `size` that add up to `sum`
+pub fn combinations(size: usize, count: usize, sum: usize) -> Vec<u32> {
+    let mut combos = vec![];
+    extend_combination(1, size, count, sum, 0, &mut combos);
+    combos
+}
+
+fn extend_combination(
+    from: usize,
+    size: usize,
+    count: usize,
+    sum: usize,
+    combo: u32,
+    combos: &mut Vec<u32>,
+) {
+    if count == 0 {
+        if sum == 0 {
+            combos.push(combo);
+        }
+        return;
+    }
+    // there must be enough digits left, and the largest of them must still reach the sum
+    if from + count > size + 1 || count * size - count * (count - 1) / 2 < sum {
+        return;
+    }
+    for d in from..=size {
+        // and the smallest digits must not overshoot it
+        if count * d + count * (count - 1) / 2 > sum {
+            break;
+        }
+        extend_combination(d + 1, size, count - 1, sum - d, combo | 1 << d, combos);
+    }
+}
+
+/// Narrow down the candidate masks of some cells to the digits that fit one of `sets`, with
+/// every cell taking a different digit of the set. `None` if no set fits.
+pub(crate) fn fitting_digits(
+    masks: &[u32],
+    sets: impl IntoIterator<Item = u32>,
+) -> Option<Vec<u32>> {
+    let mut fitting = vec![0u32; masks.len()];
+    let mut any_fits = false;
+    for set in sets {
+        if !can_match(masks, set) {
+            continue;
+        }
+        any_fits = true;
+        for (k, mask) in masks.iter().enumerate() {
+            let others: Vec<u32> = masks[..k]
+                .iter()
+                .chain(masks[k + 1..].iter())
+                .cloned()
+                .collect();
+            for d in digits_of(mask & set & !fitting[k]) {
+                if can_match(&others, set & !(1 << d)) {
+                    fitting[k] |= 1 << d;
+                }
+            }
+        }
+    }
+    if any_fits {
+        Some(fitting)
+    } else {
+        None
+    }
+}
+
+/// Whether every cell, given by its candidate mask, can take a different digit of `digits`
+fn can_match(masks: &[u32], digits: u32) -> bool {
+    match masks.split_first() {
+        None => true,
+        Some((mask, rest)) => digits_of(mask & digits).any(|d| can_match(rest, digits & !(1 << d))),
+    }
+}
+
+/// The digits in a mask, low to high
+pub(crate) fn digits_of(mask: u32) -> impl Iterator<Item = u32> {
+    (1..32).filter(move |d| mask & 1 << d != 0)
+}
+
+/// Parse a list of cages, one per line, as the sum followed by the names of its cells:
+///
+/// ```text
+/// 3: r1c1 r1c2
+/// 15: r1c3 r2c3 r3c3
+/// ```
+///
+/// Cells are named by their place in the picture of `geometry`.
+pub fn parse_cages(repr: &str, geometry: &Geometry) -> Result<Vec<Cage>, String> {
+    repr.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (sum, cells) = line
+                .split_once(':')
+                .ok_or_else(|| format!("Expected a cage such as \"3: r1c1 r1c2\", got {}", line))?;
+            let sum = sum
+                .trim()
+                .parse()
+                .map_err(|_| format!("Unexpected cage sum {}", sum.trim()))?;
+            let cells = parse_cells(cells, geometry)?;
+            Ok(Cage { sum, cells })
+        })
+        .collect()
+}
+
+/// Parse cell names such as "r1c1 r1c2", separated by spaces or commas, as laid out by `geometry`
+pub fn parse_cells(names: &str, geometry: &Geometry) -> Result<Vec<usize>, String> {
+    names
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|name| !name.is_empty())
+        .map(|name| parse_cell(name, geometry).ok_or_else(|| format!("Unexpected cell {}", name)))
+        .collect()
+}
+
+/// Index of a cell named like "r1c1", as laid out by `geometry`
+fn parse_cell(name: &str, geometry: &Geometry) -> Option<usize> {
+    let name = name.to_ascii_lowercase();
+    let (row, col) = name.strip_prefix('r')?.split_once('c')?;
+    let (row, col): (usize, usize) = (row.parse().ok()?, col.parse().ok()?);
+    geometry.cell_at(row.checked_sub(1)?, col.checked_sub(1)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::geometry::{Geometry, SAMURAI};
+
+    use super::{combinations, parse_cages, parse_cells, Cage};
+
+    #[test]
+    fn test_combinations() {
+        // 1+2 is the only way to make 3 from two digits, and 1+2+4 the only way to make 7 from three
+        assert_eq!(vec![0b110], combinations(9, 2, 3));
+        assert_eq!(vec![0b10110], combinations(9, 3, 7));
+        assert_eq!(4, combinations(9, 2, 10).len());
+        assert_eq!(vec![0b11_1111_1110], combinations(9, 9, 45));
+        assert!(combinations(9, 2, 18).is_empty());
+        assert!(combinations(4, 2, 8).is_empty());
+    }
+
+    #[test]
+    fn test_parse_cages() {
+        assert_eq!(
+            Ok(vec![
+                Cage {
+                    sum: 3,
+                    cells: vec![0, 1]
+                },
+                Cage {
+                    sum: 15,
+                    cells: vec![2, 11, 20]
+                },
+            ]),
+            parse_cages(
+                "3: r1c1 r1c2\n  15: R1C3, r2c3, r3c3\n",
+                &Geometry::standard()
+            )
+        );
+        assert!(parse_cages("3: r1c1 r1c10", &Geometry::standard()).is_err());
+        assert!(parse_cages("r1c1 r1c2", &Geometry::standard()).is_err());
+
+        // cells are named by their place in the whole picture, which has gaps between grids
+        let samurai = Geometry::overlapping(&SAMURAI).unwrap();
+        assert_eq!(
+            Ok(vec![9, 18, 27]),
+            parse_cells("r1c13 r2c1 r2c13", &samurai)
+        );
+        assert!(parse_cells("r1c10", &samurai).is_err());
+    }
+}
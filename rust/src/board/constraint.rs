@@ -0,0 +1,1021 @@
+use std::{convert::TryFrom, fmt::Debug, num::NonZeroU8, sync::Arc};
+
+use fnv::FnvHashSet;
+
+use super::{
+    cage::{combinations, fitting_digits, parse_cells},
+    geometry::Geometry,
+    Board,
+};
+
+/// A variant rule over some cells of the board, on top of the houses.
+///
+/// The solvers only go through this trait, so a new kind of clue needs no solver of its own:
+/// `prune` narrows down candidates during search and propagation, and `is_satisfied`
+/// checks the finished cells.
+pub trait Constraint: Debug + Send + Sync {
+    /// Cells the rule is about
+    fn cells(&self) -> Vec<usize>;
+
+    /// Name of the kind of rule, such as `"thermometer"`; rules of one kind on the same cells
+    /// with the same `params` are the same rule
+    fn kind(&self) -> &'static str;
+
+    /// Numbers the rule depends on besides its cells, such as the sum of a sandwich
+    fn params(&self) -> Vec<usize> {
+        vec![]
+    }
+
+    /// Whether the digits in `cells` keep the rule. Only asked once all of them are filled in.
+    fn is_satisfied(&self, board: &Board) -> bool;
+
+    /// Remove the candidates of empty cells in `cells` that cannot keep the rule with any digits
+    /// the other cells can still take. `possibles` holds the candidates of every cell of `board`,
+    /// empty for filled ones. Returns the number of candidates removed.
+    fn prune(&self, board: &Board, possibles: &mut [FnvHashSet<NonZeroU8>]) -> u32;
+
+    /// The rule as text, naming cells as laid out by `geometry`
+    fn describe(&self, geometry: &Geometry) -> String;
+}
+
+/// Constraints are told apart by their kind, cells and parameters
+impl PartialEq for dyn Constraint {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind()
+            && self.cells() == other.cells()
+            && self.params() == other.params()
+    }
+}
+
+impl Eq for dyn Constraint {}
+
+/// A thermometer: digits strictly increase from the bulb, `cells[0]`, along the rest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thermometer {
+    pub cells: Vec<usize>,
+}
+
+impl Constraint for Thermometer {
+    fn cells(&self) -> Vec<usize> {
+        self.cells.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        "thermometer"
+    }
+
+    fn is_satisfied(&self, board: &Board) -> bool {
+        self.cells
+            .windows(2)
+            .all(|pair| board.data[pair[0]].value < board.data[pair[1]].value)
+    }
+
+    fn prune(&self, board: &Board, possibles: &mut [FnvHashSet<NonZeroU8>]) -> u32 {
+        let options: Vec<Vec<u8>> = self
+            .cells
+            .iter()
+            .map(|i| options(board, possibles, *i))
+            .collect();
+
+        // the lowest digit each cell can take above the one before it, and the highest below the one after
+        let mut lows = vec![];
+        let mut low = 0;
+        for opts in options.iter() {
+            low = opts.iter().cloned().find(|v| *v > low).unwrap_or(u8::MAX);
+            lows.push(low);
+        }
+        let mut highs = vec![0; options.len()];
+        let mut high = u8::MAX;
+        for (k, opts) in options.iter().enumerate().rev() {
+            high = opts.iter().cloned().rev().find(|v| *v < high).unwrap_or(0);
+            highs[k] = high;
+        }
+
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(k, i)| retain(board, possibles, *i, |v| lows[k] <= v && v <= highs[k]))
+            .sum()
+    }
+
+    fn describe(&self, geometry: &Geometry) -> String {
+        format!("thermometer {}", cell_names(&self.cells, geometry))
+    }
+}
+
+/// An arrow: the digits along the arrow add up to the digit in its circle, `cells[0]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arrow {
+    pub cells: Vec<usize>,
+}
+
+impl Constraint for Arrow {
+    fn cells(&self) -> Vec<usize> {
+        self.cells.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        "arrow"
+    }
+
+    fn is_satisfied(&self, board: &Board) -> bool {
+        let digit = |i: &usize| board.data[*i].value.map_or(0, |v| v.get() as usize);
+        digit(&self.cells[0]) == self.cells[1..].iter().map(digit).sum::<usize>()
+    }
+
+    fn prune(&self, board: &Board, possibles: &mut [FnvHashSet<NonZeroU8>]) -> u32 {
+        let options: Vec<Vec<u8>> = self
+            .cells
+            .iter()
+            .map(|i| options(board, possibles, *i))
+            .collect();
+        if options.iter().any(|opts| opts.is_empty()) {
+            // a cell without candidates is a contradiction already
+            return 0;
+        }
+
+        let lows: Vec<usize> = options.iter().map(|opts| opts[0] as usize).collect();
+        let highs: Vec<usize> = options
+            .iter()
+            .map(|opts| *opts.last().unwrap() as usize)
+            .collect();
+        let (low, high): (usize, usize) = (lows[1..].iter().sum(), highs[1..].iter().sum());
+
+        let mut nremoved = retain(board, possibles, self.cells[0], |v| {
+            (low..=high).contains(&(v as usize))
+        });
+        for k in 1..self.cells.len() {
+            // the rest of the arrow must make up the circle less this cell
+            let rest = (low - lows[k])..=(high - highs[k]);
+            nremoved += retain(board, possibles, self.cells[k], |v| {
+                options[0]
+                    .iter()
+                    .any(|c| *c >= v && rest.contains(&((c - v) as usize)))
+            });
+        }
+        nremoved
+    }
+
+    fn describe(&self, geometry: &Geometry) -> String {
+        format!(
+            "arrow from {} along {}",
+            geometry.cell_name(self.cells[0]),
+            cell_names(&self.cells[1..], geometry)
+        )
+    }
+}
+
+/// A German whispers line: digits next to each other on the line differ by at least 5
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Whisper {
+    pub cells: Vec<usize>,
+}
+
+/// How far apart neighbors on a whispers line must be
+const WHISPER_GAP: u8 = 5;
+
+impl Constraint for Whisper {
+    fn cells(&self) -> Vec<usize> {
+        self.cells.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        "whispers line"
+    }
+
+    fn is_satisfied(&self, board: &Board) -> bool {
+        self.cells.windows(2).all(|pair| {
+            match (board.data[pair[0]].value, board.data[pair[1]].value) {
+                (Some(a), Some(b)) => a.get().abs_diff(b.get()) >= WHISPER_GAP,
+                _ => false,
+            }
+        })
+    }
+
+    fn prune(&self, board: &Board, possibles: &mut [FnvHashSet<NonZeroU8>]) -> u32 {
+        let options: Vec<Vec<u8>> = self
+            .cells
+            .iter()
+            .map(|i| options(board, possibles, *i))
+            .collect();
+        let far_from = |v: u8, k: usize| options[k].iter().any(|w| v.abs_diff(*w) >= WHISPER_GAP);
+
+        let last = self.cells.len() - 1;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(k, i)| {
+                retain(board, possibles, *i, |v| {
+                    (k == 0 || far_from(v, k - 1)) && (k == last || far_from(v, k + 1))
+                })
+            })
+            .sum()
+    }
+
+    fn describe(&self, geometry: &Geometry) -> String {
+        format!("whispers line {}", cell_names(&self.cells, geometry))
+    }
+}
+
+/// A renban line: the line holds a set of consecutive digits, in any order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Renban {
+    pub cells: Vec<usize>,
+}
+
+impl Constraint for Renban {
+    fn cells(&self) -> Vec<usize> {
+        self.cells.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        "renban line"
+    }
+
+    fn is_satisfied(&self, board: &Board) -> bool {
+        let mut digits: Vec<u8> = self
+            .cells
+            .iter()
+            .filter_map(|i| board.data[*i].value.map(|v| v.get()))
+            .collect();
+        digits.sort_unstable();
+        digits.len() == self.cells.len() && digits.windows(2).all(|pair| pair[0] + 1 == pair[1])
+    }
+
+    fn prune(&self, board: &Board, possibles: &mut [FnvHashSet<NonZeroU8>]) -> u32 {
+        let masks: Vec<u32> = self
+            .cells
+            .iter()
+            .map(|i| {
+                options(board, possibles, *i)
+                    .iter()
+                    .fold(0, |mask, v| mask | 1 << v)
+            })
+            .collect();
+
+        // the line holds one of the runs of as many consecutive digits as it has cells
+        let len = self.cells.len();
+        let runs = (1..=(board.geometry().size() + 1).saturating_sub(len))
+            .map(|low| ((1u32 << len) - 1) << low);
+        let fitting = fitting_digits(&masks, runs).unwrap_or_else(|| vec![0; len]);
+
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(k, i)| retain(board, possibles, *i, |v| fitting[k] & 1 << v != 0))
+            .sum()
+    }
+
+    fn describe(&self, geometry: &Geometry) -> String {
+        format!("renban line {}", cell_names(&self.cells, geometry))
+    }
+}
+
+/// A palindrome line: the line reads the same from both ends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palindrome {
+    pub cells: Vec<usize>,
+}
+
+impl Constraint for Palindrome {
+    fn cells(&self) -> Vec<usize> {
+        self.cells.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        "palindrome line"
+    }
+
+    fn is_satisfied(&self, board: &Board) -> bool {
+        self.cells
+            .iter()
+            .zip(self.cells.iter().rev())
+            .all(|(a, b)| board.data[*a].value == board.data[*b].value)
+    }
+
+    fn prune(&self, board: &Board, possibles: &mut [FnvHashSet<NonZeroU8>]) -> u32 {
+        let mut nremoved = 0;
+        for (a, b) in self.cells.iter().zip(self.cells.iter().rev()) {
+            let (from_a, from_b) = (options(board, possibles, *a), options(board, possibles, *b));
+            nremoved += retain(board, possibles, *a, |v| from_b.contains(&v))
+                + retain(board, possibles, *b, |v| from_a.contains(&v));
+        }
+        nremoved
+    }
+
+    fn describe(&self, geometry: &Geometry) -> String {
+        format!("palindrome line {}", cell_names(&self.cells, geometry))
+    }
+}
+
+/// How the digits of two neighboring cells relate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PairRule {
+    /// A white kropki dot: the digits are consecutive
+    WhiteDot,
+    /// A black kropki dot: one digit is double the other
+    BlackDot,
+    /// An X: the digits add up to 10
+    X,
+    /// A V: the digits add up to 5
+    V,
+    /// A greater-than sign: the first digit is the larger
+    Greater,
+}
+
+impl PairRule {
+    fn holds(&self, a: u8, b: u8) -> bool {
+        match self {
+            PairRule::WhiteDot => a + 1 == b || b + 1 == a,
+            PairRule::BlackDot => a == 2 * b || b == 2 * a,
+            PairRule::X => a + b == 10,
+            PairRule::V => a + b == 5,
+            PairRule::Greater => a > b,
+        }
+    }
+}
+
+/// A clue between two orthogonally neighboring cells
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pair {
+    pub rule: PairRule,
+    pub cells: [usize; 2],
+}
+
+impl Constraint for Pair {
+    fn cells(&self) -> Vec<usize> {
+        self.cells.to_vec()
+    }
+
+    fn kind(&self) -> &'static str {
+        match self.rule {
+            PairRule::WhiteDot => "white dot",
+            PairRule::BlackDot => "black dot",
+            PairRule::X => "X",
+            PairRule::V => "V",
+            PairRule::Greater => "greater",
+        }
+    }
+
+    fn is_satisfied(&self, board: &Board) -> bool {
+        match (
+            board.data[self.cells[0]].value,
+            board.data[self.cells[1]].value,
+        ) {
+            (Some(a), Some(b)) => self.rule.holds(a.get(), b.get()),
+            _ => false,
+        }
+    }
+
+    fn prune(&self, board: &Board, possibles: &mut [FnvHashSet<NonZeroU8>]) -> u32 {
+        let [a, b] = self.cells;
+        let (from_a, from_b) = (options(board, possibles, a), options(board, possibles, b));
+        let rule = self.rule;
+        retain(board, possibles, a, |v| {
+            from_b.iter().any(|w| rule.holds(v, *w))
+        }) + retain(board, possibles, b, |w| {
+            from_a.iter().any(|v| rule.holds(*v, w))
+        })
+    }
+
+    fn describe(&self, geometry: &Geometry) -> String {
+        let (a, b) = (
+            geometry.cell_name(self.cells[0]),
+            geometry.cell_name(self.cells[1]),
+        );
+        match self.rule {
+            PairRule::WhiteDot => format!("white dot between {} and {}", a, b),
+            PairRule::BlackDot => format!("black dot between {} and {}", a, b),
+            PairRule::X => format!("X between {} and {}", a, b),
+            PairRule::V => format!("V between {} and {}", a, b),
+            PairRule::Greater => format!("{} > {}", a, b),
+        }
+    }
+}
+
+/// A sandwich clue outside a row or column: the digits between its smallest and largest
+/// digit, 1 and 9 on a 9x9 grid, add up to `sum`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sandwich {
+    pub sum: usize,
+    /// The whole row or column, in order from the clue
+    pub cells: Vec<usize>,
+}
+
+impl Constraint for Sandwich {
+    fn cells(&self) -> Vec<usize> {
+        self.cells.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        "sandwich"
+    }
+
+    fn params(&self) -> Vec<usize> {
+        vec![self.sum]
+    }
+
+    fn is_satisfied(&self, board: &Board) -> bool {
+        let digits: Vec<usize> = self
+            .cells
+            .iter()
+            .map(|i| board.data[*i].value.map_or(0, |v| v.get() as usize))
+            .collect();
+        let position = |d: usize| digits.iter().position(|v| *v == d);
+        match (position(1), position(board.geometry().size())) {
+            (Some(p), Some(q)) => digits[p.min(q) + 1..p.max(q)].iter().sum::<usize>() == self.sum,
+            _ => false,
+        }
+    }
+
+    fn prune(&self, board: &Board, possibles: &mut [FnvHashSet<NonZeroU8>]) -> u32 {
+        let size = board.geometry().size();
+        let masks: Vec<u32> = self
+            .cells
+            .iter()
+            .map(|i| {
+                options(board, possibles, *i)
+                    .iter()
+                    .fold(0, |mask, v| mask | 1 << v)
+            })
+            .collect();
+        let (low, high) = (1u32 << 1, 1u32 << size);
+
+        // try every pair of places for the smallest and largest digit, and keep what fits any
+        let mut fitting = vec![0; masks.len()];
+        for p in (0..masks.len()).filter(|p| masks[*p] & low != 0) {
+            for q in (0..masks.len()).filter(|q| *q != p && masks[*q] & high != 0) {
+                let (a, b) = (p.min(q), p.max(q));
+                let sets = combinations(size, b - a - 1, self.sum)
+                    .into_iter()
+                    .filter(|set| set & (low | high) == 0);
+                let between = match fitting_digits(&masks[a + 1..b], sets) {
+                    Some(between) => between,
+                    None => continue,
+                };
+                let outside = |k: usize| masks[k] & !(low | high);
+                if (0..a).chain(b + 1..masks.len()).any(|k| outside(k) == 0) {
+                    continue;
+                }
+                fitting[p] |= low;
+                fitting[q] |= high;
+                for (k, fits) in (a + 1..b).zip(between) {
+                    fitting[k] |= fits;
+                }
+                for k in (0..a).chain(b + 1..masks.len()) {
+                    fitting[k] |= outside(k);
+                }
+            }
+        }
+
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(k, i)| retain(board, possibles, *i, |v| fitting[k] & 1 << v != 0))
+            .sum()
+    }
+
+    fn describe(&self, geometry: &Geometry) -> String {
+        format!(
+            "sandwich of {} on {} to {}",
+            self.sum,
+            geometry.cell_name(self.cells[0]),
+            geometry.cell_name(*self.cells.last().unwrap())
+        )
+    }
+}
+
+/// A little killer clue outside the grid: the digits along the diagonal it points down add up
+/// to `sum`. Digits may repeat, unless a house rules it out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LittleKiller {
+    pub sum: usize,
+    /// The diagonal, in order from the clue
+    pub cells: Vec<usize>,
+}
+
+impl Constraint for LittleKiller {
+    fn cells(&self) -> Vec<usize> {
+        self.cells.clone()
+    }
+
+    fn kind(&self) -> &'static str {
+        "little killer"
+    }
+
+    fn params(&self) -> Vec<usize> {
+        vec![self.sum]
+    }
+
+    fn is_satisfied(&self, board: &Board) -> bool {
+        let digit = |i: &usize| board.data[*i].value.map_or(0, |v| v.get() as usize);
+        self.cells.iter().map(digit).sum::<usize>() == self.sum
+    }
+
+    fn prune(&self, board: &Board, possibles: &mut [FnvHashSet<NonZeroU8>]) -> u32 {
+        let options: Vec<Vec<u8>> = self
+            .cells
+            .iter()
+            .map(|i| options(board, possibles, *i))
+            .collect();
+
+        // sums the cells before each cell can make, and the cells after it
+        let reach = |reached: &Vec<bool>, opts: &Vec<u8>| {
+            let mut next = vec![false; self.sum + 1];
+            for (total, _) in reached.iter().enumerate().filter(|(_, r)| **r) {
+                for v in opts.iter().map(|v| *v as usize) {
+                    if total + v <= self.sum {
+                        next[total + v] = true;
+                    }
+                }
+            }
+            next
+        };
+        let mut none = vec![false; self.sum + 1];
+        none[0] = true;
+        let mut before = vec![none.clone()];
+        for opts in options.iter() {
+            before.push(reach(before.last().unwrap(), opts));
+        }
+        let mut after = vec![none];
+        for opts in options.iter().rev() {
+            after.push(reach(after.last().unwrap(), opts));
+        }
+        after.reverse();
+
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(k, i)| {
+                retain(board, possibles, *i, |v| {
+                    let rest = match self.sum.checked_sub(v as usize) {
+                        Some(rest) => rest,
+                        None => return false,
+                    };
+                    (0..=rest).any(|total| before[k][total] && after[k + 1][rest - total])
+                })
+            })
+            .sum()
+    }
+
+    fn describe(&self, geometry: &Geometry) -> String {
+        format!(
+            "little killer of {} on {} to {}",
+            self.sum,
+            geometry.cell_name(self.cells[0]),
+            geometry.cell_name(*self.cells.last().unwrap())
+        )
+    }
+}
+
+/// Digits a cell can still take, low to high: its own if it is filled, else its candidates
+fn options(board: &Board, possibles: &[FnvHashSet<NonZeroU8>], cell: usize) -> Vec<u8> {
+    match board.data[cell].value {
+        Some(v) => vec![v.get()],
+        None => {
+            let mut opts: Vec<u8> = possibles[cell].iter().map(|v| v.get()).collect();
+            opts.sort_unstable();
+            opts
+        }
+    }
+}
+
+/// Keep the candidates of a cell that `allowed` accepts. Returns the number removed.
+fn retain(
+    board: &Board,
+    possibles: &mut [FnvHashSet<NonZeroU8>],
+    cell: usize,
+    allowed: impl Fn(u8) -> bool,
+) -> u32 {
+    if board.data[cell].value.is_some() {
+        return 0;
+    }
+    let before = possibles[cell].len();
+    possibles[cell].retain(|v| allowed(v.get()));
+    (before - possibles[cell].len()) as u32
+}
+
+fn cell_names(cells: &[usize], geometry: &Geometry) -> String {
+    cells
+        .iter()
+        .map(|i| geometry.cell_name(*i))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Parse a constraint written as a label followed by its cells, as laid out by `geometry`:
+/// - `thermo: r1c1 r1c2 r1c3`, from the bulb up
+/// - `arrow: r1c1 r2c2 r3c3`, from the circle along the arrow
+/// - `white: r1c1 r1c2` or `black: r1c1 r1c2` for kropki dots
+/// - `x: r1c1 r1c2` or `v: r1c1 r1c2`
+/// - `greater: r1c1 r1c2`, where the first cell holds the larger digit
+/// - `whisper: ...`, `renban: ...` or `palindrome: ...` for lines, in order along the line
+///
+/// Outside clues name the spot next to the grid they are written in, with row or column 0
+/// above or left of the grid, and one past the last below or right of it:
+/// - `sandwich: r0c3 15` for the column below, or `sandwich: r4c10 0` for the row to the left
+/// - `little killer: r0c2 se 23` for the diagonal it points down, `ne`, `nw`, `se` or `sw`
+pub fn parse_constraint(line: &str, geometry: &Geometry) -> Result<Arc<dyn Constraint>, String> {
+    let (label, cells) = line.split_once(':').ok_or_else(|| {
+        format!(
+            "Expected a constraint such as \"thermo: r1c1 r1c2\", got {}",
+            line
+        )
+    })?;
+    let label = label.trim().to_ascii_lowercase();
+    if label == "sandwich" || label == "little killer" {
+        return parse_outside_clue(&label, cells, geometry);
+    }
+    let cells = parse_cells(cells, geometry)?;
+
+    let pair_rule = match label.as_str() {
+        "thermo" | "arrow" | "whisper" | "renban" | "palindrome" => None,
+        "white" => Some(PairRule::WhiteDot),
+        "black" => Some(PairRule::BlackDot),
+        "x" => Some(PairRule::X),
+        "v" => Some(PairRule::V),
+        "greater" => Some(PairRule::Greater),
+        _ => return Err(format!("Unknown constraint {}", label)),
+    };
+    match pair_rule {
+        Some(rule) => {
+            if cells.len() != 2 || !is_orthogonal(cells[0], cells[1], geometry) {
+                return Err(format!("A {} needs two neighboring cells", label));
+            }
+            Ok(Arc::new(Pair {
+                rule,
+                cells: [cells[0], cells[1]],
+            }))
+        }
+        None => {
+            if cells.len() < 2 {
+                return Err(format!("A {} needs at least two cells", label));
+            }
+            match label.as_str() {
+                "thermo" => Ok(Arc::new(Thermometer { cells })),
+                "arrow" => Ok(Arc::new(Arrow { cells })),
+                "whisper" => Ok(Arc::new(Whisper { cells })),
+                "renban" => Ok(Arc::new(Renban { cells })),
+                _ => Ok(Arc::new(Palindrome { cells })),
+            }
+        }
+    }
+}
+
+/// Parse the spot, direction and sum of a sandwich or little killer clue
+fn parse_outside_clue(
+    label: &str,
+    clue: &str,
+    geometry: &Geometry,
+) -> Result<Arc<dyn Constraint>, String> {
+    let words: Vec<&str> = clue.split_whitespace().collect();
+    let expected = || match label {
+        "sandwich" => format!(
+            "Expected a sandwich such as \"r0c3 15\", got {}",
+            clue.trim()
+        ),
+        _ => format!(
+            "Expected a little killer such as \"r0c2 se 23\", got {}",
+            clue.trim()
+        ),
+    };
+    let spot = words
+        .first()
+        .and_then(|w| parse_spot(w))
+        .ok_or_else(expected)?;
+    let sum: usize = words
+        .last()
+        .and_then(|w| w.parse().ok())
+        .ok_or_else(expected)?;
+    let name = words[0].to_ascii_lowercase();
+    let first_cell = |step: &(isize, isize)| cell_at(geometry, (spot.0 + step.0, spot.1 + step.1));
+    if first_cell(&(0, 0)).is_some() {
+        return Err(format!("Clue {} is inside the grid", name));
+    }
+
+    if label == "sandwich" {
+        if words.len() != 2 {
+            return Err(expected());
+        }
+        let steps: Vec<(isize, isize)> = [(1, 0), (-1, 0), (0, 1), (0, -1)]
+            .iter()
+            .cloned()
+            .filter(|step| first_cell(step).is_some())
+            .collect();
+        if steps.len() != 1 {
+            return Err(format!(
+                "Sandwich {} is not next to one row or column",
+                name
+            ));
+        }
+        let cells = walk(geometry, spot, steps[0]);
+        if cells.len() != geometry.size() {
+            return Err(format!("Sandwich {} needs a whole row or column", name));
+        }
+        return Ok(Arc::new(Sandwich { sum, cells }));
+    }
+
+    let step = match words.get(1).map(|w| w.to_ascii_lowercase()).as_deref() {
+        Some("ne") if words.len() == 3 => (-1, 1),
+        Some("nw") if words.len() == 3 => (-1, -1),
+        Some("se") if words.len() == 3 => (1, 1),
+        Some("sw") if words.len() == 3 => (1, -1),
+        _ => return Err(expected()),
+    };
+    let cells = walk(geometry, spot, step);
+    if cells.is_empty() {
+        return Err(format!("Little killer {} points away from the grid", name));
+    }
+    Ok(Arc::new(LittleKiller { sum, cells }))
+}
+
+/// Row and column of a spot written like a cell, counted from 0 so that the edges around the
+/// grid can be named
+fn parse_spot(name: &str) -> Option<(isize, isize)> {
+    let name = name.to_ascii_lowercase();
+    let (row, col) = name.strip_prefix('r')?.split_once('c')?;
+    Some((
+        row.parse::<isize>().ok()? - 1,
+        col.parse::<isize>().ok()? - 1,
+    ))
+}
+
+fn cell_at(geometry: &Geometry, (row, col): (isize, isize)) -> Option<usize> {
+    geometry.cell_at(usize::try_from(row).ok()?, usize::try_from(col).ok()?)
+}
+
+/// Cells met going from `spot` by `step` until the grid ends
+fn walk(geometry: &Geometry, spot: (isize, isize), step: (isize, isize)) -> Vec<usize> {
+    let mut cells = vec![];
+    let (mut row, mut col) = (spot.0 + step.0, spot.1 + step.1);
+    while let Some(cell) = cell_at(geometry, (row, col)) {
+        cells.push(cell);
+        row += step.0;
+        col += step.1;
+    }
+    cells
+}
+
+/// Whether two cells share a side
+fn is_orthogonal(a: usize, b: usize, geometry: &Geometry) -> bool {
+    let ((ra, ca), (rb, cb)) = (geometry.coords(a), geometry.coords(b));
+    ra.abs_diff(rb) + ca.abs_diff(cb) == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroU8, sync::Arc};
+
+    use fnv::FnvHashSet;
+
+    use crate::board::{geometry::Geometry, Board};
+
+    use super::{
+        parse_constraint, Arrow, Constraint, LittleKiller, Pair, PairRule, Palindrome, Renban,
+        Sandwich, Thermometer, Whisper,
+    };
+
+    /// Candidates of every cell of an empty 9x9 board
+    fn all_possibles() -> Vec<FnvHashSet<NonZeroU8>> {
+        vec![Geometry::standard().digits().collect(); 81]
+    }
+
+    fn sorted(possibles: &FnvHashSet<NonZeroU8>) -> Vec<u8> {
+        let mut values: Vec<u8> = possibles.iter().map(|v| v.get()).collect();
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn test_thermometer() {
+        let board = Board::empty(Arc::new(Geometry::standard()));
+        let thermo = Thermometer {
+            cells: vec![0, 1, 2],
+        };
+        let mut possibles = all_possibles();
+        possibles[2].retain(|v| v.get() <= 5);
+        assert_eq!(6 + 6 + 2, thermo.prune(&board, &mut possibles));
+        assert_eq!(vec![1, 2, 3], sorted(&possibles[0]));
+        assert_eq!(vec![2, 3, 4], sorted(&possibles[1]));
+        assert_eq!(vec![3, 4, 5], sorted(&possibles[2]));
+    }
+
+    #[test]
+    fn test_arrow() {
+        let mut board = Board::empty(Arc::new(Geometry::standard()));
+        board.data[0].value = NonZeroU8::new(4);
+        let arrow = Arrow {
+            cells: vec![0, 1, 2],
+        };
+        let mut possibles = all_possibles();
+        possibles[0].clear();
+        arrow.prune(&board, &mut possibles);
+        assert_eq!(vec![1, 2, 3], sorted(&possibles[1]));
+        assert_eq!(vec![1, 2, 3], sorted(&possibles[2]));
+
+        board.data[1].value = NonZeroU8::new(3);
+        board.data[2].value = NonZeroU8::new(1);
+        assert!(arrow.is_satisfied(&board));
+        assert_eq!(
+            "arrow from r1c1 along r1c2 r1c3",
+            arrow.describe(board.geometry())
+        );
+    }
+
+    #[test]
+    fn test_pairs() {
+        let board = Board::empty(Arc::new(Geometry::standard()));
+        let expected = [
+            (PairRule::WhiteDot, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]),
+            (PairRule::BlackDot, vec![1, 2, 3, 4, 6, 8]),
+            // the pair does not know the cells share a row, so 5 stays with 5
+            (PairRule::X, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]),
+            (PairRule::V, vec![1, 2, 3, 4]),
+            (PairRule::Greater, vec![2, 3, 4, 5, 6, 7, 8, 9]),
+        ];
+        for (rule, values) in expected.iter() {
+            let pair = Pair {
+                rule: *rule,
+                cells: [0, 1],
+            };
+            let mut possibles = all_possibles();
+            pair.prune(&board, &mut possibles);
+            assert_eq!(*values, sorted(&possibles[0]), "{:?}", rule);
+        }
+    }
+
+    #[test]
+    fn test_whisper() {
+        let mut board = Board::empty(Arc::new(Geometry::standard()));
+        let whisper = Whisper {
+            cells: vec![0, 1, 2],
+        };
+        let mut possibles = all_possibles();
+        // 5 is at most 4 away from any digit
+        assert_eq!(3, whisper.prune(&board, &mut possibles));
+        possibles[1].retain(|v| v.get() <= 2);
+        whisper.prune(&board, &mut possibles);
+        assert_eq!(vec![6, 7, 8, 9], sorted(&possibles[0]));
+        assert_eq!(vec![6, 7, 8, 9], sorted(&possibles[2]));
+
+        board.data[0].value = NonZeroU8::new(7);
+        board.data[1].value = NonZeroU8::new(2);
+        board.data[2].value = NonZeroU8::new(6);
+        assert!(!whisper.is_satisfied(&board));
+        board.data[2].value = NonZeroU8::new(8);
+        assert!(whisper.is_satisfied(&board));
+    }
+
+    #[test]
+    fn test_renban() {
+        let mut board = Board::empty(Arc::new(Geometry::standard()));
+        let renban = Renban {
+            cells: vec![0, 10, 20],
+        };
+        board.data[0].value = NonZeroU8::new(9);
+        let mut possibles = all_possibles();
+        possibles[0].clear();
+        renban.prune(&board, &mut possibles);
+        assert_eq!(vec![7, 8], sorted(&possibles[10]));
+        assert_eq!(vec![7, 8], sorted(&possibles[20]));
+
+        board.data[10].value = NonZeroU8::new(7);
+        board.data[20].value = NonZeroU8::new(8);
+        assert!(renban.is_satisfied(&board));
+        board.data[20].value = NonZeroU8::new(6);
+        assert!(!renban.is_satisfied(&board));
+    }
+
+    #[test]
+    fn test_palindrome() {
+        let mut board = Board::empty(Arc::new(Geometry::standard()));
+        let palindrome = Palindrome {
+            cells: vec![0, 9, 18, 27],
+        };
+        board.data[0].value = NonZeroU8::new(4);
+        let mut possibles = all_possibles();
+        possibles[0].clear();
+        possibles[9].retain(|v| v.get() <= 2);
+        assert_eq!(8 + 7, palindrome.prune(&board, &mut possibles));
+        assert_eq!(vec![4], sorted(&possibles[27]));
+        assert_eq!(vec![1, 2], sorted(&possibles[18]));
+        assert_eq!(
+            "palindrome line r1c1 r2c1 r3c1 r4c1",
+            palindrome.describe(board.geometry())
+        );
+    }
+
+    #[test]
+    fn test_sandwich() {
+        let mut board = Board::empty(Arc::new(Geometry::standard()));
+        let row = Sandwich {
+            sum: 35,
+            cells: (0..9).collect(),
+        };
+        // 2 to 8 add up to 35, so the 1 and 9 are at the ends
+        let mut possibles = all_possibles();
+        assert_eq!(7 + 7 + 2 * 7, row.prune(&board, &mut possibles));
+        assert_eq!(vec![1, 9], sorted(&possibles[0]));
+        assert_eq!(vec![2, 3, 4, 5, 6, 7, 8], sorted(&possibles[4]));
+
+        // 6 next to a 1 is 6 alone, or 2 and 4
+        let row = Sandwich {
+            sum: 6,
+            cells: (0..9).collect(),
+        };
+        board.data[0].value = NonZeroU8::new(1);
+        let mut possibles = all_possibles();
+        possibles[0].clear();
+        row.prune(&board, &mut possibles);
+        assert_eq!(vec![2, 4, 6], sorted(&possibles[1]));
+        assert_eq!(vec![2, 4, 9], sorted(&possibles[2]));
+        assert!(!possibles[8].contains(&NonZeroU8::new(9).unwrap()));
+
+        for (i, v) in [6, 9, 2, 3, 4, 5, 7, 8].iter().enumerate() {
+            board.data[i + 1].value = NonZeroU8::new(*v);
+        }
+        assert!(row.is_satisfied(&board));
+        board.data.swap(1, 3);
+        assert!(!row.is_satisfied(&board));
+    }
+
+    #[test]
+    fn test_little_killer() {
+        let mut board = Board::empty(Arc::new(Geometry::standard()));
+        let diagonal = LittleKiller {
+            sum: 6,
+            cells: vec![0, 10, 20],
+        };
+        // digits may repeat, so the others can be as low as 1 and 1
+        let mut possibles = all_possibles();
+        assert_eq!(3 * 5, diagonal.prune(&board, &mut possibles));
+        assert_eq!(vec![1, 2, 3, 4], sorted(&possibles[10]));
+
+        board.data[0].value = NonZeroU8::new(3);
+        possibles[0].clear();
+        possibles[20].retain(|v| v.get() != 2);
+        diagonal.prune(&board, &mut possibles);
+        assert_eq!(vec![2], sorted(&possibles[10]));
+        assert_eq!(vec![1], sorted(&possibles[20]));
+
+        board.data[10].value = NonZeroU8::new(2);
+        board.data[20].value = NonZeroU8::new(1);
+        assert!(diagonal.is_satisfied(&board));
+        assert_eq!(
+            "little killer of 6 on r1c1 to r3c3",
+            diagonal.describe(board.geometry())
+        );
+    }
+
+    #[test]
+    fn test_parse_outside_clue() {
+        let geometry = Geometry::standard();
+        let column = parse_constraint("sandwich: r0c3 15", &geometry).unwrap();
+        assert_eq!(
+            (0..9).map(|r| r * 9 + 2).collect::<Vec<_>>(),
+            column.cells()
+        );
+        let row = parse_constraint("Sandwich: r4c10 0", &geometry).unwrap();
+        assert_eq!((27..36).rev().collect::<Vec<_>>(), row.cells());
+        assert_eq!("sandwich of 0 on r4c9 to r4c1", row.describe(&geometry));
+        let diagonal = parse_constraint("little killer: r0c2 SE 23", &geometry).unwrap();
+        assert_eq!(vec![2, 12, 22, 32, 42, 52, 62], diagonal.cells());
+        let corner = parse_constraint("little killer: r10c10 nw 45", &geometry).unwrap();
+        assert_eq!(vec![80, 70, 60, 50, 40, 30, 20, 10, 0], corner.cells());
+        assert!(*column == *parse_constraint("Sandwich: r0c3 15", &geometry).unwrap());
+        assert!(*column != *parse_constraint("sandwich: r0c3 16", &geometry).unwrap());
+
+        assert!(parse_constraint("sandwich: r1c1 5", &geometry).is_err());
+        assert!(parse_constraint("sandwich: r0c0 5", &geometry).is_err());
+        assert!(parse_constraint("sandwich: r0c3", &geometry).is_err());
+        assert!(parse_constraint("little killer: r0c2 23", &geometry).is_err());
+        assert_eq!(
+            Err("Little killer r0c2 points away from the grid".to_owned()),
+            parse_constraint("little killer: r0c2 ne 23", &geometry).map(|c| c.cells())
+        );
+    }
+
+    #[test]
+    fn test_parse_constraint() {
+        let geometry = Geometry::standard();
+        let thermo = parse_constraint("thermo: r1c1 r1c2 r2c3", &geometry).unwrap();
+        assert_eq!(vec![0, 1, 11], thermo.cells());
+        assert_eq!("thermometer r1c1 r1c2 r2c3", thermo.describe(&geometry));
+        let black = parse_constraint("Black: r1c1, r2c1", &geometry).unwrap();
+        assert_eq!("black dot between r1c1 and r2c1", black.describe(&geometry));
+        assert!(*black == *parse_constraint("black: r1c1 r2c1", &geometry).unwrap());
+        assert!(*black != *parse_constraint("white: r1c1 r2c1", &geometry).unwrap());
+
+        assert!(parse_constraint("x: r1c1 r2c2", &geometry).is_err());
+        assert!(parse_constraint("arrow: r1c1", &geometry).is_err());
+        assert!(parse_constraint("killer: r1c1 r1c2", &geometry).is_err());
+
+        let renban = parse_constraint("renban: r1c1 r2c2 r3c3", &geometry).unwrap();
+        assert_eq!("renban line r1c1 r2c2 r3c3", renban.describe(&geometry));
+        let whisper = parse_constraint("Whisper: r1c1 r2c2", &geometry).unwrap();
+        assert_eq!("whispers line r1c1 r2c2", whisper.describe(&geometry));
+        assert!(parse_constraint("palindrome: r1c1", &geometry).is_err());
+        // the same cells make a different rule of another kind
+        assert!(*renban != *parse_constraint("thermo: r1c1 r2c2 r3c3", &geometry).unwrap());
+    }
+}
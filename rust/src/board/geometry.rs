@@ -0,0 +1,1193 @@
+use std::{num::NonZeroU8, sync::Arc};
+
+use super::{
+    cage::{combinations, Cage},
+    constraint::Constraint,
+    restriction::Restriction,
+};
+
+/// Symbols used for digits, in order; grids with more than 9 digits continue with letters
+pub const SYMBOLS: &str = "123456789ABCDEFGHIJKLMNOP";
+
+/// What a house is, for naming it and for techniques that care about box/line interactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HouseKind {
+    Row,
+    Column,
+    Box,
+    /// An irregular region of a jigsaw sudoku, in place of a box
+    Region,
+    /// A main diagonal of Sudoku-X
+    Diagonal,
+    /// A window of Windoku: a box-shaped block set one cell in from the boxes
+    Window,
+    /// A disjoint group: the cells at the same place in every box
+    Group,
+    /// Any other set of cells given as a house by the puzzle
+    Extra,
+}
+
+impl HouseKind {
+    fn name(&self) -> &'static str {
+        match self {
+            HouseKind::Row => "row",
+            HouseKind::Column => "column",
+            HouseKind::Box => "box",
+            HouseKind::Region => "region",
+            HouseKind::Diagonal => "diagonal",
+            HouseKind::Window => "window",
+            HouseKind::Group => "disjoint group",
+            HouseKind::Extra => "extra region",
+        }
+    }
+
+    /// Whether the house plays the part of a box: a compact block that crosses rows and columns
+    pub fn is_box(&self) -> bool {
+        matches!(self, HouseKind::Box | HouseKind::Region | HouseKind::Window)
+    }
+}
+
+/// A global rule of chess sudoku: the same digit may not appear a chess piece's move apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChessMove {
+    Knight,
+    King,
+}
+
+impl ChessMove {
+    /// Name of the rule, such as "anti-knight"
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChessMove::Knight => "anti-knight",
+            ChessMove::King => "anti-king",
+        }
+    }
+
+    /// Row and column steps of the move
+    fn offsets(&self) -> [(isize, isize); 8] {
+        match self {
+            ChessMove::Knight => [
+                (-2, -1),
+                (-2, 1),
+                (-1, -2),
+                (-1, 2),
+                (1, -2),
+                (1, 2),
+                (2, -1),
+                (2, 1),
+            ],
+            ChessMove::King => [
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ],
+        }
+    }
+}
+
+/// Where the cells of a puzzle sit in its picture, the rectangle it is drawn in.
+/// A single grid fills its picture; grids that overlap, as in Samurai, leave gaps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Layout {
+    /// columns of the picture
+    width: usize,
+    /// the cell at each spot of the picture, in reading order, or `None` for a gap
+    picture: Vec<Option<usize>>,
+    /// for every cell, its row and column in the picture
+    coords: Vec<(usize, usize)>,
+    /// top left corners of the grids, each as wide as a row; just `(0, 0)` for a single grid
+    grids: Vec<(usize, usize)>,
+}
+
+impl Layout {
+    /// One grid `size` wide
+    fn single(size: usize) -> Self {
+        Self::overlapping(size, &[(0, 0)])
+    }
+
+    /// Grids `size` wide with their top left corners at `grids`, numbering the cells
+    /// in reading order of the picture
+    fn overlapping(size: usize, grids: &[(usize, usize)]) -> Self {
+        let height = grids.iter().map(|(top, _)| top + size).max().unwrap_or(0);
+        let width = grids.iter().map(|(_, left)| left + size).max().unwrap_or(0);
+        let mut picture = vec![None; height * width];
+        let mut coords = vec![];
+        for row in 0..height {
+            for col in 0..width {
+                if grids.iter().any(|(top, left)| {
+                    (*top..top + size).contains(&row) && (*left..left + size).contains(&col)
+                }) {
+                    picture[row * width + col] = Some(coords.len());
+                    coords.push((row, col));
+                }
+            }
+        }
+        Self {
+            width,
+            picture,
+            coords,
+            grids: grids.to_vec(),
+        }
+    }
+
+    fn cell_at(&self, row: usize, col: usize) -> Option<usize> {
+        if col < self.width {
+            self.picture.get(row * self.width + col).cloned().flatten()
+        } else {
+            None
+        }
+    }
+
+    /// The cell a step of `(rows, columns)` away from a cell, if it lands on one
+    fn step(&self, cell: usize, (dr, dc): (isize, isize)) -> Option<usize> {
+        let (r, c) = self.coords[cell];
+        let (r, c) = (r.checked_add_signed(dr)?, c.checked_add_signed(dc)?);
+        self.cell_at(r, c)
+    }
+
+    /// Whether `cells` form one orthogonally connected piece
+    fn is_connected(&self, cells: &[usize]) -> bool {
+        let mut reached = vec![cells[0]];
+        let mut frontier = vec![cells[0]];
+        while let Some(cell) = frontier.pop() {
+            for dir in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                if let Some(next) = self.step(cell, dir) {
+                    if cells.contains(&next) && !reached.contains(&next) {
+                        reached.push(next);
+                        frontier.push(next);
+                    }
+                }
+            }
+        }
+        reached.len() == cells.len()
+    }
+}
+
+/// The rows, then the columns, of a single grid `size` wide
+fn lines(size: usize) -> (Vec<Vec<usize>>, Vec<HouseKind>) {
+    let mut houses = vec![];
+    let mut kinds = vec![];
+    for r in 0..size {
+        houses.push((0..size).map(|c| r * size + c).collect());
+        kinds.push(HouseKind::Row);
+    }
+    for c in 0..size {
+        houses.push((0..size).map(|r| r * size + c).collect());
+        kinds.push(HouseKind::Column);
+    }
+    (houses, kinds)
+}
+
+/// Top left corners of the five grids of Samurai sudoku: four in the corners of the picture,
+/// each sharing a box with the one in the middle
+pub const SAMURAI: [(usize, usize); 5] = [(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)];
+
+/// Top left corners of twin sudoku: two grids sharing one corner box
+pub const TWIN: [(usize, usize); 2] = [(0, 0), (6, 6)];
+
+/// Top left corners of butterfly sudoku: four grids in a 12x12 picture, each overlapping
+/// the others by two thirds or more
+pub const BUTTERFLY: [(usize, usize); 4] = [(0, 0), (0, 3), (3, 0), (3, 3)];
+
+/// Shape of a puzzle: its cells, the houses that must each hold every digit once,
+/// any killer cages, chess rules, restrictions or other variant constraints, and the peers of each cell (the cells
+/// it shares a house or cage with, or that are a forbidden move away).
+///
+/// Cells are numbered in reading order, and houses are listed rows first, then columns, then boxes
+/// (or jigsaw regions), then any extra houses of a variant. A puzzle of several overlapping
+/// grids, as made by `Geometry::overlapping`, numbers its cells in reading order of the whole
+/// picture, skipping the gaps, and lists the rows, columns and boxes of every grid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Geometry {
+    /// number of digits, which is also the length of every row, column and house
+    size: usize,
+    layout: Layout,
+    /// rows and columns of a box, if the boxes are rectangles
+    box_shape: Option<(usize, usize)>,
+    houses: Vec<Vec<usize>>,
+    kinds: Vec<HouseKind>,
+    /// for every cell, the houses containing it
+    cell_houses: Vec<Vec<usize>>,
+    cages: Vec<Cage>,
+    /// for every cell, the cage containing it
+    cell_cages: Vec<Option<usize>>,
+    chess_moves: Vec<ChessMove>,
+    constraints: Vec<Arc<dyn Constraint>>,
+    /// for every cell, the constraints it is in
+    cell_constraints: Vec<Vec<usize>>,
+    restrictions: Vec<Restriction>,
+    /// for every cell, its peers, sorted low to high
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl Geometry {
+    /// The usual 9x9 grid with 3x3 boxes
+    pub fn standard() -> Self {
+        Self::square(3)
+    }
+
+    /// An n²×n² grid with n×n boxes, such as 4x4 (`box_size` 2) or 16x16 (`box_size` 4)
+    pub fn square(box_size: usize) -> Self {
+        Self::rectangular(box_size, box_size)
+    }
+
+    /// A grid with boxes `box_rows` high and `box_cols` wide, such as 6x6 with 2x3 boxes.
+    /// Rows and columns are as long as a box is big.
+    pub fn rectangular(box_rows: usize, box_cols: usize) -> Self {
+        let size = box_rows * box_cols;
+        assert!(
+            size <= SYMBOLS.len(),
+            "grids larger than 25x25 are not supported"
+        );
+
+        let (mut houses, mut kinds) = lines(size);
+        // boxes are numbered in reading order, with `box_rows` boxes across
+        for b in 0..size {
+            let (top, left) = (b / box_rows * box_rows, b % box_rows * box_cols);
+            houses.push(
+                (0..size)
+                    .map(|i| (top + i / box_cols) * size + left + i % box_cols)
+                    .collect(),
+            );
+            kinds.push(HouseKind::Box);
+        }
+
+        Self {
+            box_shape: Some((box_rows, box_cols)),
+            ..Self::from_houses(size, Layout::single(size), houses, kinds)
+        }
+    }
+
+    /// A Latin square `size` wide: every digit once in each row and column, with no boxes.
+    /// Any size up to 25 works, such as 5 or 7, which have no box shape.
+    pub fn latin(size: usize) -> Self {
+        assert!(
+            size <= SYMBOLS.len(),
+            "grids larger than 25x25 are not supported"
+        );
+        let (houses, kinds) = lines(size);
+        Self::from_houses(size, Layout::single(size), houses, kinds)
+    }
+
+    /// Several 9x9 grids with 3x3 boxes drawn overlapping in one picture, such as Samurai
+    /// with the corners in `SAMURAI`. `grids` are the top left corners of the grids, which
+    /// must fall on the lines of a 3x3 box pattern, so that overlapping grids share whole boxes.
+    /// Every grid has its own rows, columns and boxes, and a box shared by grids is one house.
+    pub fn overlapping(grids: &[(usize, usize)]) -> Result<Self, String> {
+        let (size, box_size) = (9, 3);
+        if grids.is_empty() {
+            return Err("Expected at least one grid".to_owned());
+        }
+        for (g, (top, left)) in grids.iter().enumerate() {
+            if top % box_size != 0 || left % box_size != 0 {
+                return Err(format!(
+                    "Grid {} at r{}c{} does not line up with the boxes",
+                    g + 1,
+                    top + 1,
+                    left + 1
+                ));
+            }
+            if grids[..g].contains(&(*top, *left)) {
+                return Err(format!("Grid {} is drawn twice", g + 1));
+            }
+        }
+
+        let layout = Layout::overlapping(size, grids);
+        let grid_cell = |(top, left): (usize, usize), r: usize, c: usize| {
+            layout.cell_at(top + r, left + c).unwrap()
+        };
+        let mut houses = vec![];
+        let mut kinds = vec![];
+        for grid in grids.iter() {
+            for r in 0..size {
+                houses.push((0..size).map(|c| grid_cell(*grid, r, c)).collect());
+                kinds.push(HouseKind::Row);
+            }
+        }
+        for grid in grids.iter() {
+            for c in 0..size {
+                houses.push((0..size).map(|r| grid_cell(*grid, r, c)).collect());
+                kinds.push(HouseKind::Column);
+            }
+        }
+        for grid in grids.iter() {
+            for b in 0..size {
+                let (top, left) = (b / box_size * box_size, b % box_size * box_size);
+                let cells: Vec<usize> = (0..size)
+                    .map(|i| grid_cell(*grid, top + i / box_size, left + i % box_size))
+                    .collect();
+                if !houses.contains(&cells) {
+                    houses.push(cells);
+                    kinds.push(HouseKind::Box);
+                }
+            }
+        }
+
+        Ok(Self {
+            box_shape: Some((box_size, box_size)),
+            ..Self::from_houses(size, layout, houses, kinds)
+        })
+    }
+
+    /// The usual layout for a grid with `size` digits: square boxes if `size` is a square,
+    /// otherwise boxes as close to square as possible and wider than they are high (2x3 for 6x6).
+    /// `None` if `size` is prime, so no boxes fit, or too big for the symbols.
+    pub fn for_size(size: usize) -> Option<Self> {
+        let box_rows = (2..=size)
+            .take_while(|r| r * r <= size)
+            .filter(|r| size.is_multiple_of(*r))
+            .last()?;
+        if size > SYMBOLS.len() {
+            return None;
+        }
+        Some(Self::rectangular(box_rows, size / box_rows))
+    }
+
+    /// A jigsaw grid, where `regions[i]` is the region of the cell at index `i`.
+    /// Every region must have as many cells as a row, and be connected orthogonally.
+    pub fn jigsaw(regions: &[usize]) -> Result<Self, String> {
+        let size = (1..=SYMBOLS.len())
+            .find(|n| n * n == regions.len())
+            .ok_or_else(|| format!("Expected a square region map, got {} cells", regions.len()))?;
+
+        let layout = Layout::single(size);
+        let (mut houses, mut kinds) = lines(size);
+        for region in 0..size {
+            let cells: Vec<usize> = (0..regions.len())
+                .filter(|i| regions[*i] == region)
+                .collect();
+            if cells.len() != size {
+                return Err(format!(
+                    "Region {} has {} cells, expected {}",
+                    region + 1,
+                    cells.len(),
+                    size
+                ));
+            }
+            if !layout.is_connected(&cells) {
+                return Err(format!("Region {} is not connected", region + 1));
+            }
+            houses.push(cells);
+            kinds.push(HouseKind::Region);
+        }
+
+        Ok(Self::from_houses(size, layout, houses, kinds))
+    }
+
+    /// A jigsaw grid from a region map, with one symbol per cell in reading order, such as
+    /// a 9x9 grid of the letters A to I. Regions are numbered in the order they first appear.
+    pub fn from_region_map(repr: &str) -> Result<Self, String> {
+        let mut names: Vec<char> = vec![];
+        let regions: Vec<usize> = repr
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| match names.iter().position(|n| *n == c) {
+                Some(region) => region,
+                None => {
+                    names.push(c);
+                    names.len() - 1
+                }
+            })
+            .collect();
+        Self::jigsaw(&regions)
+    }
+
+    /// The same grid with both main diagonals as extra houses, as in Sudoku-X.
+    /// Every grid of an overlapping puzzle gets its own.
+    pub fn with_diagonals(self) -> Self {
+        let size = self.size;
+        let mut diagonals = vec![];
+        for (top, left) in self.layout.grids.iter() {
+            let cell = |r: usize, c: usize| self.layout.cell_at(top + r, left + c).unwrap();
+            diagonals.push((0..size).map(|i| cell(i, i)).collect());
+            diagonals.push((0..size).map(|i| cell(i, size - 1 - i)).collect());
+        }
+        self.with_houses(diagonals, HouseKind::Diagonal)
+    }
+
+    /// The same grid with killer cages. Every cell must be in exactly one cage, each cage must be
+    /// connected and able to reach its sum with different digits, and the sums must add up to
+    /// the total of all digits on the grid, which the boxes (or regions) split up evenly,
+    /// or the rows of a grid without boxes.
+    pub fn with_cages(self, cages: Vec<Cage>) -> Result<Self, String> {
+        let size = self.size;
+        let mut cell_cages = vec![None; self.n_cells()];
+        for (c, cage) in cages.iter().enumerate() {
+            for i in cage.cells.iter() {
+                match cell_cages.get(*i) {
+                    None => return Err(format!("Cage {} has a cell outside the grid", c + 1)),
+                    Some(Some(other)) => {
+                        return Err(format!(
+                            "{} is in both cage {} and cage {}",
+                            self.cell_name(*i),
+                            other + 1,
+                            c + 1
+                        ))
+                    }
+                    Some(None) => cell_cages[*i] = Some(c),
+                }
+            }
+            if cage.cells.is_empty() || !self.layout.is_connected(&cage.cells) {
+                return Err(format!("Cage {} is not connected", c + 1));
+            }
+            if combinations(size, cage.cells.len(), cage.sum).is_empty() {
+                return Err(format!(
+                    "Cage {} cannot make {} from {} different digits",
+                    c + 1,
+                    cage.sum,
+                    cage.cells.len()
+                ));
+            }
+        }
+        if let Some(i) = cell_cages.iter().position(|c| c.is_none()) {
+            return Err(format!("{} is not in any cage", self.cell_name(i)));
+        }
+        let total: usize = cages.iter().map(|cage| cage.sum).sum();
+        // boxes, or else rows, split the grid into parts holding every digit once
+        let nparts = match self
+            .kinds
+            .iter()
+            .filter(|k| matches!(k, HouseKind::Box | HouseKind::Region))
+            .count()
+        {
+            0 => self.houses_of_kind(HouseKind::Row).count(),
+            nboxes => nboxes,
+        };
+        if total != nparts * size * (size + 1) / 2 {
+            return Err(format!(
+                "Cage sums add up to {}, expected {}",
+                total,
+                nparts * size * (size + 1) / 2
+            ));
+        }
+
+        Ok(Self {
+            cages,
+            cell_cages,
+            ..self
+        }
+        .with_peers())
+    }
+
+    /// The same grid with the windows of Windoku (hyper sudoku) as extra houses: blocks of the
+    /// box shape, starting one cell in from the top left and one cell apart, such as the four
+    /// windows at r2c2, r2c6, r6c2 and r6c6 of a 9x9 grid. Needs rectangular boxes.
+    /// Every grid of an overlapping puzzle gets its own.
+    pub fn with_windows(self) -> Result<Self, String> {
+        let (box_rows, box_cols) = self
+            .box_shape
+            .ok_or_else(|| "Windoku needs rectangular boxes".to_owned())?;
+        let size = self.size;
+        let starts = |len: usize| {
+            (0..)
+                .map(move |k| 1 + k * (len + 1))
+                .take_while(move |start| start + len <= size)
+        };
+
+        let mut windows = vec![];
+        for (grid_top, grid_left) in self.layout.grids.iter() {
+            for top in starts(box_rows) {
+                for left in starts(box_cols) {
+                    windows.push(
+                        (0..size)
+                            .map(|i| {
+                                let (r, c) = (top + i / box_cols, left + i % box_cols);
+                                self.layout.cell_at(grid_top + r, grid_left + c).unwrap()
+                            })
+                            .collect(),
+                    );
+                }
+            }
+        }
+        if windows.is_empty() {
+            return Err("The grid is too small for windows".to_owned());
+        }
+        Ok(self.with_houses(windows, HouseKind::Window))
+    }
+
+    /// The same grid with disjoint groups as extra houses: for each place in a box, the cells at
+    /// that place in every box, such as r1c1, r1c4, ..., r7c7 for the top left corners of a
+    /// 9x9 grid. Needs rectangular boxes. Every grid of an overlapping puzzle gets its own.
+    pub fn with_disjoint_groups(self) -> Result<Self, String> {
+        let (box_rows, box_cols) = self
+            .box_shape
+            .ok_or_else(|| "Disjoint groups need rectangular boxes".to_owned())?;
+        let size = self.size;
+
+        let mut groups = vec![];
+        for (grid_top, grid_left) in self.layout.grids.iter() {
+            for place in 0..size {
+                groups.push(
+                    (0..size)
+                        .map(|b| {
+                            // boxes are numbered as in `Geometry::rectangular`
+                            let (top, left) = (b / box_rows * box_rows, b % box_rows * box_cols);
+                            let (r, c) = (top + place / box_cols, left + place % box_cols);
+                            self.layout.cell_at(grid_top + r, grid_left + c).unwrap()
+                        })
+                        .collect(),
+                );
+            }
+        }
+        Ok(self.with_houses(groups, HouseKind::Group))
+    }
+
+    /// The same grid with `regions` as extra houses. Each region needs as many different cells
+    /// as a row, but can have any shape.
+    pub fn with_regions(self, regions: Vec<Vec<usize>>) -> Result<Self, String> {
+        for (r, region) in regions.iter().enumerate() {
+            let mut cells = region.clone();
+            cells.sort_unstable();
+            cells.dedup();
+            if cells.len() != self.size || region.len() != self.size {
+                return Err(format!(
+                    "Extra region {} has {} different cells, expected {}",
+                    r + 1,
+                    cells.len(),
+                    self.size
+                ));
+            }
+            if cells.iter().any(|i| *i >= self.n_cells()) {
+                return Err(format!(
+                    "Extra region {} has a cell outside the grid",
+                    r + 1
+                ));
+            }
+        }
+        Ok(self.with_houses(regions, HouseKind::Extra))
+    }
+
+    /// The same grid where a digit may not repeat a `rule` move apart, such as in anti-knight sudoku
+    pub fn with_chess_move(mut self, rule: ChessMove) -> Self {
+        if !self.chess_moves.contains(&rule) {
+            self.chess_moves.push(rule);
+        }
+        self.with_peers()
+    }
+
+    /// The same grid with a variant constraint, such as a thermometer
+    pub fn with_constraint(mut self, constraint: Arc<dyn Constraint>) -> Result<Self, String> {
+        let cells = constraint.cells();
+        if cells.iter().any(|i| *i >= self.n_cells()) {
+            return Err(format!(
+                "{} has a cell outside the grid",
+                constraint.describe(&self)
+            ));
+        }
+        for i in cells {
+            self.cell_constraints[i].push(self.constraints.len());
+        }
+        self.constraints.push(constraint);
+        Ok(self)
+    }
+
+    /// The same grid with a restriction on the digits of cells, such as non-consecutive.
+    /// A cell may be shaded even or odd, but not both.
+    pub fn with_restriction(mut self, restriction: Restriction) -> Result<Self, String> {
+        match restriction {
+            Restriction::NonConsecutive => {}
+            Restriction::Even(cell) | Restriction::Odd(cell) => {
+                if cell >= self.n_cells() {
+                    return Err(format!("Shaded cell {} is outside the grid", cell));
+                }
+                let opposite = match restriction {
+                    Restriction::Even(_) => Restriction::Odd(cell),
+                    _ => Restriction::Even(cell),
+                };
+                if self.restrictions.contains(&opposite) {
+                    return Err(format!(
+                        "{} is shaded both even and odd",
+                        self.cell_name(cell)
+                    ));
+                }
+            }
+        }
+        if !self.restrictions.contains(&restriction) {
+            self.restrictions.push(restriction);
+        }
+        Ok(self)
+    }
+
+    /// The same grid with `extra` houses of `kind` after the existing ones
+    fn with_houses(self, extra: Vec<Vec<usize>>, kind: HouseKind) -> Self {
+        let (mut houses, mut kinds) = (self.houses, self.kinds);
+        for house in extra {
+            houses.push(house);
+            kinds.push(kind);
+        }
+        Self {
+            box_shape: self.box_shape,
+            cages: self.cages,
+            cell_cages: self.cell_cages,
+            chess_moves: self.chess_moves,
+            constraints: self.constraints,
+            cell_constraints: self.cell_constraints,
+            restrictions: self.restrictions,
+            ..Self::from_houses(self.size, self.layout, houses, kinds)
+        }
+        .with_peers()
+    }
+
+    fn from_houses(
+        size: usize,
+        layout: Layout,
+        houses: Vec<Vec<usize>>,
+        kinds: Vec<HouseKind>,
+    ) -> Self {
+        let n_cells = layout.coords.len();
+        let mut cell_houses = vec![vec![]; n_cells];
+        for (h, house) in houses.iter().enumerate() {
+            for i in house.iter() {
+                cell_houses[*i].push(h);
+            }
+        }
+
+        Self {
+            size,
+            layout,
+            box_shape: None,
+            houses,
+            kinds,
+            cell_houses,
+            cages: vec![],
+            cell_cages: vec![None; n_cells],
+            chess_moves: vec![],
+            constraints: vec![],
+            cell_constraints: vec![vec![]; n_cells],
+            restrictions: vec![],
+            neighbors: vec![],
+        }
+        .with_peers()
+    }
+
+    /// Work out the peers of every cell from its houses, cage and chess moves
+    fn with_peers(mut self) -> Self {
+        self.neighbors = (0..self.n_cells())
+            .map(|i| {
+                let mut peers: Vec<usize> = self.cell_houses[i]
+                    .iter()
+                    .flat_map(|h| self.houses[*h].iter().cloned())
+                    .chain(
+                        self.cell_cages[i]
+                            .iter()
+                            .flat_map(|c| self.cages[*c].cells.iter().cloned()),
+                    )
+                    .chain(
+                        self.chess_moves
+                            .iter()
+                            .flat_map(|rule| self.move_targets(i, *rule)),
+                    )
+                    .filter(|j| *j != i)
+                    .collect();
+                peers.sort_unstable();
+                peers.dedup();
+                peers
+            })
+            .collect();
+        self
+    }
+
+    /// Number of digits, and the length of a row
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Rows and columns of a box, such as `(2, 3)` for 6x6 sudoku
+    pub fn box_shape(&self) -> Option<(usize, usize)> {
+        self.box_shape
+    }
+
+    pub fn n_cells(&self) -> usize {
+        self.layout.coords.len()
+    }
+
+    /// Rows and columns of the picture the grid is drawn in, which is `size` by `size`
+    /// unless several grids overlap
+    pub fn picture_shape(&self) -> (usize, usize) {
+        let width = self.layout.width;
+        (self.layout.picture.len() / width, width)
+    }
+
+    /// Row and column of a cell in the picture, counting from 0
+    pub fn coords(&self, cell: usize) -> (usize, usize) {
+        self.layout.coords[cell]
+    }
+
+    /// The cell at a row and column of the picture, counting from 0, if there is one
+    pub fn cell_at(&self, row: usize, col: usize) -> Option<usize> {
+        self.layout.cell_at(row, col)
+    }
+
+    /// Top left corners of the grids in the picture; just `(0, 0)` unless several grids overlap
+    pub fn grids(&self) -> &[(usize, usize)] {
+        &self.layout.grids
+    }
+
+    /// Whether `cells` form one orthogonally connected piece
+    pub fn is_connected(&self, cells: &[usize]) -> bool {
+        self.layout.is_connected(cells)
+    }
+
+    /// All the digits that can go on the grid
+    pub fn digits(&self) -> impl Iterator<Item = NonZeroU8> {
+        (1..=self.size as u8).map(|v| NonZeroU8::new(v).unwrap())
+    }
+
+    pub fn houses(&self) -> &[Vec<usize>] {
+        &self.houses
+    }
+
+    pub fn house(&self, house: usize) -> &[usize] {
+        &self.houses[house]
+    }
+
+    pub fn kind(&self, house: usize) -> HouseKind {
+        self.kinds[house]
+    }
+
+    /// Indices of the houses of a kind
+    pub fn houses_of_kind(&self, kind: HouseKind) -> impl Iterator<Item = usize> + '_ {
+        (0..self.houses.len()).filter(move |h| self.kinds[*h] == kind)
+    }
+
+    /// Indices of the houses containing a cell
+    pub fn houses_of(&self, cell: usize) -> &[usize] {
+        &self.cell_houses[cell]
+    }
+
+    /// The house of a kind that contains a cell
+    pub fn house_of_kind(&self, cell: usize, kind: HouseKind) -> Option<usize> {
+        self.cell_houses[cell]
+            .iter()
+            .cloned()
+            .find(|h| self.kinds[*h] == kind)
+    }
+
+    /// Index of the first house containing both cells
+    pub fn shared_house(&self, a: usize, b: usize) -> Option<usize> {
+        self.cell_houses[a]
+            .iter()
+            .cloned()
+            .find(|h| self.houses[*h].contains(&b))
+    }
+
+    /// Killer cages, if any
+    pub fn cages(&self) -> &[Cage] {
+        &self.cages
+    }
+
+    /// Index of the cage containing a cell
+    pub fn cage_of(&self, cell: usize) -> Option<usize> {
+        self.cell_cages[cell]
+    }
+
+    /// Name of a cage with its sum, such as "cage 1 (15)" for index 0
+    pub fn cage_name(&self, cage: usize) -> String {
+        format!("cage {} ({})", cage + 1, self.cages[cage].sum)
+    }
+
+    /// Variant constraints, if any
+    pub fn constraints(&self) -> &[Arc<dyn Constraint>] {
+        &self.constraints
+    }
+
+    /// Indices of the constraints a cell is in
+    pub fn constraints_of(&self, cell: usize) -> &[usize] {
+        &self.cell_constraints[cell]
+    }
+
+    /// Restrictions on the digits of cells, if any
+    pub fn restrictions(&self) -> &[Restriction] {
+        &self.restrictions
+    }
+
+    /// Chess rules in force, if any
+    pub fn chess_moves(&self) -> &[ChessMove] {
+        &self.chess_moves
+    }
+
+    /// Cells a `rule` move away from a cell
+    pub fn move_targets(&self, cell: usize, rule: ChessMove) -> Vec<usize> {
+        rule.offsets()
+            .iter()
+            .filter_map(|offset| self.layout.step(cell, *offset))
+            .collect()
+    }
+
+    /// The chess rule in force that keeps two cells from holding the same digit
+    pub fn chess_move_between(&self, a: usize, b: usize) -> Option<ChessMove> {
+        self.chess_moves
+            .iter()
+            .cloned()
+            .find(|rule| self.move_targets(a, *rule).contains(&b))
+    }
+
+    /// Cells that share a side with a cell, in the picture
+    pub fn orthogonal_neighbors(&self, cell: usize) -> Vec<usize> {
+        [(-1, 0), (0, -1), (0, 1), (1, 0)]
+            .iter()
+            .filter_map(|dir| self.layout.step(cell, *dir))
+            .collect()
+    }
+
+    /// Cells that share a house or cage with a cell, or are a forbidden move away from it,
+    /// sorted low to high
+    pub fn neighbors(&self, cell: usize) -> &[usize] {
+        &self.neighbors[cell]
+    }
+
+    /// Whether two different cells are peers
+    pub fn sees(&self, a: usize, b: usize) -> bool {
+        self.neighbors[a].binary_search(&b).is_ok()
+    }
+
+    /// Name of a cell by its place in the picture, such as "r1c1" for index 0
+    pub fn cell_name(&self, cell: usize) -> String {
+        let (r, c) = self.layout.coords[cell];
+        format!("r{}c{}", r + 1, c + 1)
+    }
+
+    /// Name of a house, such as "row 1" for index 0
+    pub fn house_name(&self, house: usize) -> String {
+        let kind = self.kinds[house];
+        let ordinal = self.kinds[..house].iter().filter(|k| **k == kind).count() + 1;
+        format!("{} {}", kind.name(), ordinal)
+    }
+
+    /// Symbol shown for a digit
+    pub fn symbol(&self, value: NonZeroU8) -> char {
+        SYMBOLS.as_bytes()[value.get() as usize - 1] as char
+    }
+
+    /// Digit shown by a symbol; letters are case-insensitive
+    pub fn value_of(&self, symbol: char) -> Option<NonZeroU8> {
+        SYMBOLS
+            .find(symbol.to_ascii_uppercase())
+            .filter(|i| *i < self.size)
+            .map(|i| NonZeroU8::new(i as u8 + 1).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::{
+        cage::{parse_cages, Cage},
+        positions::{DIAGONALS, DISJOINT_GROUPS, HOUSES, NEIGHBORS, N_LINES},
+    };
+
+    use super::{ChessMove, Geometry, HouseKind, BUTTERFLY, SAMURAI, TWIN};
+
+    #[test]
+    fn test_standard_matches_tables() {
+        let g = Geometry::standard();
+        assert_eq!(81, g.n_cells());
+        for (h, house) in HOUSES.iter().enumerate() {
+            assert_eq!(house.to_vec(), g.house(h));
+        }
+        for (i, ns) in NEIGHBORS.iter().enumerate() {
+            let ns: Vec<usize> = ns.iter().map(|n| *n as usize).collect();
+            assert_eq!(ns, g.neighbors(i));
+        }
+        assert_eq!("box 5", g.house_name(22));
+        assert_eq!("r2c3", g.cell_name(11));
+    }
+
+    #[test]
+    fn test_other_sizes() {
+        let g = Geometry::square(2);
+        assert_eq!(16, g.n_cells());
+        assert_eq!(12, g.houses().len());
+        assert!(g.neighbors(0).iter().all(|n| g.neighbors(*n).contains(&0)));
+        assert_eq!(7, g.neighbors(0).len());
+        assert_eq!(Some(8), g.house_of_kind(0, HouseKind::Box));
+
+        let g = Geometry::square(4);
+        assert_eq!(39, g.neighbors(0).len());
+        assert_eq!('G', g.symbol(g.value_of('g').unwrap()));
+        assert_eq!(None, g.value_of('H'));
+    }
+
+    #[test]
+    fn test_rectangular_boxes() {
+        let g = Geometry::rectangular(2, 3);
+        assert_eq!(6, g.size());
+        assert_eq!(Some((2, 3)), g.box_shape());
+        // the second box sits to the right of the first, the third below it
+        assert_eq!(&[3, 4, 5, 9, 10, 11], g.house(13));
+        assert_eq!(&[12, 13, 14, 18, 19, 20], g.house(14));
+        // 5 in the row, 5 in the column, and the 2 cells of the box on the other row
+        assert_eq!(12, g.neighbors(0).len());
+
+        assert_eq!(Some((3, 4)), Geometry::for_size(12).unwrap().box_shape());
+        assert_eq!(Some((2, 4)), Geometry::for_size(8).unwrap().box_shape());
+        assert_eq!(Geometry::standard(), Geometry::for_size(9).unwrap());
+        assert_eq!(None, Geometry::for_size(7));
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let g = Geometry::standard().with_diagonals();
+        assert_eq!(29, g.houses().len());
+        assert_eq!(&DIAGONALS[0], g.house(27));
+        assert_eq!(&DIAGONALS[1], g.house(28));
+        assert_eq!("diagonal 2", g.house_name(28));
+        // r1c1 also sees the rest of its diagonal outside box 1
+        assert_eq!(26, g.neighbors(0).len());
+        // the centre sees every other cell on both diagonals
+        assert!(g.sees(40, 8) && g.sees(40, 80));
+        assert_eq!(Some((3, 3)), g.box_shape());
+    }
+
+    #[test]
+    fn test_windows() {
+        let g = Geometry::standard().with_windows().unwrap();
+        assert_eq!(31, g.houses().len());
+        assert_eq!(&[10, 11, 12, 19, 20, 21, 28, 29, 30], g.house(27));
+        assert_eq!(&[46, 47, 48, 55, 56, 57, 64, 65, 66], g.house(29));
+        assert_eq!(HouseKind::Window, g.kind(30));
+        assert!(g.kind(30).is_box());
+        assert_eq!("window 4", g.house_name(30));
+        // r2c2 now also sees r4c4 through its window
+        assert!(g.sees(10, 30));
+        assert_eq!(4, g.houses_of(10).len());
+        // the middle cells of the rows and columns between windows are in no window
+        assert_eq!(3, g.houses_of(40).len());
+
+        let g = Geometry::square(2).with_windows().unwrap();
+        assert_eq!(vec![vec![5, 6, 9, 10]], g.houses()[12..].to_vec());
+
+        let jigsaw = Geometry::jigsaw(&[0, 0, 1, 1, 0, 0, 1, 1, 2, 2, 3, 3, 2, 2, 3, 3]).unwrap();
+        assert!(jigsaw.with_windows().is_err());
+    }
+
+    #[test]
+    fn test_latin() {
+        let g = Geometry::latin(9);
+        assert_eq!(18, g.houses().len());
+        for (h, house) in HOUSES[..N_LINES].iter().enumerate() {
+            assert_eq!(house.to_vec(), g.house(h));
+        }
+        assert_eq!(16, g.neighbors(0).len());
+        assert_eq!(None, g.box_shape());
+        assert_eq!(None, g.house_of_kind(0, HouseKind::Box));
+        assert!(g.clone().with_windows().is_err());
+        assert!(g.with_disjoint_groups().is_err());
+
+        // no box shape fits 7, but a Latin square does not need one
+        let g = Geometry::latin(7);
+        assert_eq!(49, g.n_cells());
+        assert_eq!(12, g.neighbors(48).len());
+        // with no boxes, the cages must add up to the rows instead
+        let rows: Vec<Cage> = (0..7)
+            .map(|r| Cage {
+                sum: 28,
+                cells: g.house(r).to_vec(),
+            })
+            .collect();
+        assert!(g.clone().with_cages(rows[1..].to_vec()).is_err());
+        assert!(g.with_cages(rows).is_ok());
+    }
+
+    #[test]
+    fn test_disjoint_groups() {
+        let g = Geometry::standard().with_disjoint_groups().unwrap();
+        assert_eq!(36, g.houses().len());
+        for (k, group) in DISJOINT_GROUPS.iter().enumerate() {
+            assert_eq!(group.to_vec(), g.house(27 + k));
+        }
+        assert_eq!(HouseKind::Group, g.kind(27));
+        assert!(!g.kind(27).is_box());
+        assert_eq!("disjoint group 5", g.house_name(31));
+        // the centre sees the centres of the other boxes too, four of them new
+        assert!(g.sees(40, 10) && g.sees(40, 70));
+        assert_eq!(24, g.neighbors(40).len());
+
+        // 2x3 boxes: the top left corners of the six boxes
+        let g = Geometry::rectangular(2, 3).with_disjoint_groups().unwrap();
+        assert_eq!(&[0, 3, 12, 15, 24, 27], g.house(18));
+    }
+
+    #[test]
+    fn test_overlapping() {
+        let g = Geometry::overlapping(&SAMURAI).unwrap();
+        assert_eq!((21, 21), g.picture_shape());
+        assert_eq!(369, g.n_cells());
+        // five grids of 9 rows, 9 columns and 9 boxes, with the 4 shared boxes counted once
+        assert_eq!(45 + 45 + 41, g.houses().len());
+        assert_eq!(Some(9), g.cell_at(0, 12));
+        assert_eq!(None, g.cell_at(0, 9));
+        assert_eq!(None, g.cell_at(21, 0));
+        assert_eq!((20, 20), g.coords(368));
+        assert_eq!("r21c21", g.cell_name(368));
+
+        // r7c7 is in the corner box of the first grid and the middle one, so it is in a row
+        // and a column of each, and in one box
+        let shared = g.cell_at(6, 6).unwrap();
+        assert_eq!(&[6, 18, 51, 63, 98], g.houses_of(shared));
+        assert_eq!("row 19", g.house_name(18));
+        assert_eq!(32, g.neighbors(shared).len());
+        assert_eq!(20, g.neighbors(0).len());
+        assert!(!g.sees(shared, g.cell_at(6, 20).unwrap()));
+
+        assert_eq!(153, Geometry::overlapping(&TWIN).unwrap().n_cells());
+        let butterfly = Geometry::overlapping(&BUTTERFLY).unwrap();
+        assert_eq!(144, butterfly.n_cells());
+        assert_eq!(36 + 36 + 16, butterfly.houses().len());
+
+        // variant houses come in every grid
+        let x = Geometry::overlapping(&TWIN).unwrap().with_diagonals();
+        assert_eq!(4, x.houses_of_kind(HouseKind::Diagonal).count());
+        let windows = Geometry::overlapping(&TWIN)
+            .unwrap()
+            .with_windows()
+            .unwrap();
+        assert_eq!(8, windows.houses_of_kind(HouseKind::Window).count());
+
+        assert!(Geometry::overlapping(&[(0, 0), (4, 4)]).is_err());
+        assert!(Geometry::overlapping(&[(0, 0), (0, 0)]).is_err());
+        assert!(Geometry::overlapping(&[]).is_err());
+    }
+
+    #[test]
+    fn test_extra_regions() {
+        let g = Geometry::square(2)
+            .with_regions(vec![vec![0, 5, 10, 15], vec![1, 2, 7, 11]])
+            .unwrap();
+        assert_eq!(14, g.houses().len());
+        assert_eq!(HouseKind::Extra, g.kind(13));
+        assert!(!g.kind(13).is_box());
+        assert_eq!("extra region 2", g.house_name(13));
+        assert!(g.sees(1, 11));
+
+        assert_eq!(
+            Err("Extra region 1 has 3 different cells, expected 4".to_owned()),
+            Geometry::square(2).with_regions(vec![vec![0, 5, 5, 15]])
+        );
+        assert_eq!(
+            Err("Extra region 1 has a cell outside the grid".to_owned()),
+            Geometry::square(2).with_regions(vec![vec![0, 5, 10, 16]])
+        );
+    }
+
+    #[test]
+    fn test_chess_moves() {
+        let knight = Geometry::standard().with_chess_move(ChessMove::Knight);
+        // the knight's moves from the centre all leave its row, column and box
+        assert_eq!(28, knight.neighbors(40).len());
+        assert_eq!(vec![11, 19], knight.move_targets(0, ChessMove::Knight));
+        assert_eq!(Some(ChessMove::Knight), knight.chess_move_between(0, 19));
+        assert_eq!(None, knight.chess_move_between(0, 20));
+        assert!(knight.sees(40, 29) && !Geometry::standard().sees(40, 29));
+        assert_eq!(knight, knight.clone().with_chess_move(ChessMove::Knight));
+        assert_eq!(
+            knight.chess_moves(),
+            knight.clone().with_diagonals().chess_moves()
+        );
+
+        let king = Geometry::standard().with_chess_move(ChessMove::King);
+        // inside a box the king's moves add nothing, but from r1c3 it reaches r2c4
+        assert_eq!(20, king.neighbors(40).len());
+        assert_eq!(21, king.neighbors(2).len());
+        assert!(king.sees(2, 12));
+
+        let both = knight.with_chess_move(ChessMove::King);
+        assert_eq!(&[ChessMove::Knight, ChessMove::King], both.chess_moves());
+        assert_eq!(23, both.neighbors(2).len());
+    }
+
+    #[test]
+    fn test_jigsaw_regions() {
+        let g = Geometry::from_region_map(
+            "AAAABBCCC
+            AAABBBCCC
+            AABBBBCCC
+            DDDEEEEFF
+            DDDEEEFFF
+            DDDEEFFFF
+            GGGHHHIII
+            GGGHHHIII
+            GGGHHHIII",
+        )
+        .unwrap();
+        assert_eq!(HouseKind::Region, g.kind(18));
+        assert_eq!(&[0, 1, 2, 3, 9, 10, 11, 18, 19], g.house(18));
+        assert_eq!("region 2", g.house_name(19));
+        // r1c4 is in the first region, so it sees r3c2 but no longer r2c5
+        assert!(g.sees(3, 19));
+        assert!(!g.sees(3, 13));
+        assert_eq!(None, g.box_shape());
+
+        let err = Geometry::from_region_map(
+            "AAAABBCCC
+            AAABBBCCC
+            AABBBBCCC
+            DDDEEEEFF
+            DDDEEEFFF
+            DDDEEFFFF
+            GGGHHHIII
+            GGGHHHIII
+            GGGHHHIIA",
+        );
+        assert_eq!(Err("Region 1 has 10 cells, expected 9".to_owned()), err);
+
+        // the first region is split in two
+        let err = Geometry::from_region_map("AABB ABBA CCDD CCDD");
+        assert_eq!(Err("Region 1 is not connected".to_owned()), err);
+    }
+
+    #[test]
+    fn test_cages() {
+        let cages = "4: r1c1 r2c1
+            5: r1c2 r1c3
+            6: r1c4 r2c4
+            9: r2c2 r2c3 r3c3
+            9: r3c1 r4c1 r4c2
+            1: r3c2
+            4: r3c4 r4c4
+            2: r4c3";
+        let with_cages = |repr: &str| {
+            Geometry::square(2).with_cages(parse_cages(repr, &Geometry::square(2)).unwrap())
+        };
+
+        let g = with_cages(cages).unwrap();
+        assert_eq!(Some(3), g.cage_of(10));
+        assert_eq!("cage 4 (9)", g.cage_name(3));
+        // r2c2 and r3c3 only share a cage
+        assert!(g.sees(5, 10));
+        assert_eq!(None, g.shared_house(5, 10));
+        assert!(!Geometry::square(2).sees(5, 10));
+        // extra houses keep the cages
+        assert!(g.clone().with_diagonals().sees(5, 10));
+        assert_eq!(g.cages(), g.clone().with_diagonals().cages());
+
+        assert_eq!(
+            Err("r4c4 is in both cage 7 and cage 8".to_owned()),
+            with_cages(&cages.replace("2: r4c3", "2: r4c3 r4c4"))
+        );
+        assert_eq!(
+            Err("r4c3 is not in any cage".to_owned()),
+            with_cages(&cages.replace("2: r4c3", ""))
+        );
+        assert_eq!(
+            Err("Cage 1 is not connected".to_owned()),
+            with_cages(
+                &cages
+                    .replace("r1c1 r2c1", "r1c1 r3c2")
+                    .replace("1: r3c2", "1: r2c1")
+            )
+        );
+        assert_eq!(
+            Err("Cage 8 cannot make 5 from 1 different digits".to_owned()),
+            with_cages(&cages.replace("2: r4c3", "5: r4c3"))
+        );
+        assert_eq!(
+            Err("Cage sums add up to 41, expected 40".to_owned()),
+            with_cages(&cages.replace("2: r4c3", "3: r4c3"))
+        );
+    }
+}
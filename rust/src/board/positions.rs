@@ -1,7 +1,10 @@
 pub const N_CELLS: usize = 9 * 9;
 pub const N_HOUSES: usize = 27;
+/// Rows and columns come first in `HOUSES`, so `&HOUSES[..N_LINES]` are the houses of a
+/// 9x9 Latin square
+pub const N_LINES: usize = 18;
 
-/// All the houses in a sudoku board
+/// All the houses in a sudoku board: rows, then columns, then boxes
 pub const HOUSES: [House; N_HOUSES] = [
     [0, 1, 2, 3, 4, 5, 6, 7, 8],
     [9, 10, 11, 12, 13, 14, 15, 16, 17],
@@ -34,6 +37,26 @@ pub const HOUSES: [House; N_HOUSES] = [
 
 pub type House = [usize; 9];
 
+/// The two main diagonals, which Sudoku-X adds to `HOUSES`
+pub const DIAGONALS: [House; 2] = [
+    [0, 10, 20, 30, 40, 50, 60, 70, 80],
+    [8, 16, 24, 32, 40, 48, 56, 64, 72],
+];
+
+/// The nine disjoint groups, each the cells at one place in every box, which the
+/// disjoint-groups variant adds to `HOUSES`
+pub const DISJOINT_GROUPS: [House; 9] = [
+    [0, 3, 6, 27, 30, 33, 54, 57, 60],
+    [1, 4, 7, 28, 31, 34, 55, 58, 61],
+    [2, 5, 8, 29, 32, 35, 56, 59, 62],
+    [9, 12, 15, 36, 39, 42, 63, 66, 69],
+    [10, 13, 16, 37, 40, 43, 64, 67, 70],
+    [11, 14, 17, 38, 41, 44, 65, 68, 71],
+    [18, 21, 24, 45, 48, 51, 72, 75, 78],
+    [19, 22, 25, 46, 49, 52, 73, 76, 79],
+    [20, 23, 26, 47, 50, 53, 74, 77, 80],
+];
+
 /// list of cells that share a row, col or block for a given cell, sorted low to high
 #[rustfmt::skip]
 pub const NEIGHBORS: [[u8; 20]; 81] = [
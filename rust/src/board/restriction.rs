@@ -0,0 +1,92 @@
+use std::num::NonZeroU8;
+
+use super::{geometry::Geometry, Board};
+
+/// A variant rule that rules out digits cell by cell, from a mark on the cell or the digits
+/// next to it. Unlike a `Constraint`, it never needs the candidates of other cells, so the
+/// solvers apply it wherever they work out or place a digit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Restriction {
+    /// Orthogonally adjacent cells may not hold consecutive digits
+    NonConsecutive,
+    /// A cell shaded to hold an even digit
+    Even(usize),
+    /// A cell shaded to hold an odd digit
+    Odd(usize),
+}
+
+impl Restriction {
+    /// Whether `value` may go in `cell` of `board`, given the digits already around it
+    pub fn allows(&self, board: &Board, cell: usize, value: NonZeroU8) -> bool {
+        match self {
+            Restriction::NonConsecutive => {
+                board.geometry().orthogonal_neighbors(cell).iter().all(|n| {
+                    board.data[*n]
+                        .value
+                        .is_none_or(|v| v.get().abs_diff(value.get()) != 1)
+                })
+            }
+            Restriction::Even(shaded) => *shaded != cell || value.get().is_multiple_of(2),
+            Restriction::Odd(shaded) => *shaded != cell || !value.get().is_multiple_of(2),
+        }
+    }
+
+    /// Cells whose digits decide what `cell` may hold under the rule
+    pub fn partners(&self, geometry: &Geometry, cell: usize) -> Vec<usize> {
+        match self {
+            Restriction::NonConsecutive => geometry.orthogonal_neighbors(cell),
+            Restriction::Even(_) | Restriction::Odd(_) => vec![],
+        }
+    }
+
+    /// The rule as text, naming cells as laid out by `geometry`
+    pub fn describe(&self, geometry: &Geometry) -> String {
+        match self {
+            Restriction::NonConsecutive => "non-consecutive".to_owned(),
+            Restriction::Even(cell) => format!("even shading of {}", geometry.cell_name(*cell)),
+            Restriction::Odd(cell) => format!("odd shading of {}", geometry.cell_name(*cell)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroU8, sync::Arc};
+
+    use crate::board::{geometry::Geometry, Board};
+
+    use super::Restriction;
+
+    #[test]
+    fn test_allows() {
+        let geometry = Geometry::standard()
+            .with_restriction(Restriction::NonConsecutive)
+            .unwrap();
+        let mut board = Board::empty(Arc::new(geometry));
+        board.data[10].value = NonZeroU8::new(5);
+        let allowed = |cell: usize| -> Vec<u8> {
+            (1..=9)
+                .filter(|v| {
+                    Restriction::NonConsecutive.allows(&board, cell, NonZeroU8::new(*v).unwrap())
+                })
+                .collect()
+        };
+        // r1c2 is above r2c2, but r1c1 only touches it at a corner
+        assert_eq!(vec![1, 2, 3, 5, 7, 8, 9], allowed(1));
+        assert_eq!((1..=9).collect::<Vec<u8>>(), allowed(0));
+        assert_eq!(
+            vec![1, 9, 11, 19],
+            Restriction::NonConsecutive.partners(board.geometry(), 10)
+        );
+
+        let even = Restriction::Even(0);
+        assert!(even.allows(&board, 0, NonZeroU8::new(4).unwrap()));
+        assert!(!even.allows(&board, 0, NonZeroU8::new(3).unwrap()));
+        assert!(even.allows(&board, 1, NonZeroU8::new(3).unwrap()));
+        assert!(Restriction::Odd(0).allows(&board, 0, NonZeroU8::new(3).unwrap()));
+        assert_eq!(
+            "odd shading of r1c1",
+            Restriction::Odd(0).describe(board.geometry())
+        );
+    }
+}
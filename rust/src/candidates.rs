@@ -0,0 +1,329 @@
+use std::{
+    fmt::{self, Display},
+    num::NonZeroU8,
+};
+
+use fnv::FnvHashSet;
+
+use crate::{board::Board, naive::find_possibles};
+
+/// A board together with the pencil marks (candidates) of its empty cells
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidates {
+    pub(crate) board: Board,
+    /// candidates per cell; always empty for a filled cell
+    pub(crate) possibles: Vec<FnvHashSet<NonZeroU8>>,
+}
+
+/// Why propagation could not continue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Contradiction {
+    /// An empty cell has no candidates left
+    NoCandidates { cell: usize },
+    /// A digit has no cell left in a house, given as an index into the board's `Geometry::houses`
+    NoPlace { house: usize, value: NonZeroU8 },
+    /// A digit was put in `cell` although `peer` already holds it
+    Conflict { cell: usize, peer: usize },
+    /// A digit was assumed in `cell`, which already holds another one
+    Filled { cell: usize },
+    /// `value` was assumed in `cell`, but elimination has already removed it from its candidates
+    NotCandidate { cell: usize, value: NonZeroU8 },
+    /// No different digits among the candidates of a cage, given as an index into the board's
+    /// `Geometry::cages`, can make up its sum
+    NoCombination { cage: usize },
+    /// The cells of a variant constraint, given as an index into `Geometry::constraints`,
+    /// are all filled and break it
+    Broken { constraint: usize },
+}
+
+impl Candidates {
+    /// Fill in the candidates of every empty cell from the values of its neighbors
+    pub fn new(board: Board) -> Self {
+        let possibles = (0..board.data.len())
+            .map(|i| match board.data[i].value {
+                Some(_) => FnvHashSet::default(),
+                None => find_possibles(&board, i),
+            })
+            .collect();
+        Self { board, possibles }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn into_board(self) -> Board {
+        self.board
+    }
+
+    /// Candidates of the cell at `index`
+    pub fn get(&self, index: usize) -> &FnvHashSet<NonZeroU8> {
+        &self.possibles[index]
+    }
+
+    /// Put `value` on the cell at `index`, and remove it from the candidates of its neighbors,
+    /// and any candidates of nearby cells a restriction now rules out
+    pub fn place(&mut self, index: usize, value: NonZeroU8) {
+        self.board.data[index].value = Some(value);
+        self.possibles[index].clear();
+        for n in self.board.geometry.neighbors(index).iter() {
+            self.possibles[*n].remove(&value);
+        }
+        let geometry = self.board.geometry.clone();
+        for restriction in geometry.restrictions() {
+            for n in restriction.partners(&geometry, index) {
+                let board = &self.board;
+                self.possibles[n].retain(|v| restriction.allows(board, n, *v));
+            }
+        }
+    }
+
+    /// Remove `value` from the candidates of the cell at `index`. Returns whether it was there.
+    pub fn eliminate(&mut self, index: usize, value: NonZeroU8) -> bool {
+        self.possibles[index].remove(&value)
+    }
+
+    /// Repeatedly place naked and hidden singles until none are left, narrowing down
+    /// the candidates of killer cages and variant constraints in between.
+    /// Returns the number of placements made.
+    pub fn propagate(&mut self) -> Result<u32, Contradiction> {
+        let mut nplaced = 0;
+        loop {
+            let placed = self.place_naked_singles()? + self.place_hidden_singles()?;
+            if placed == 0 && self.restrict_cages()? + self.prune_constraints()? == 0 {
+                return Ok(nplaced);
+            }
+            nplaced += placed;
+        }
+    }
+
+    fn place_naked_singles(&mut self) -> Result<u32, Contradiction> {
+        let mut nplaced = 0;
+        for i in 0..self.possibles.len() {
+            if self.board.data[i].value.is_some() {
+                continue;
+            }
+            match self.possibles[i].len() {
+                0 => return Err(Contradiction::NoCandidates { cell: i }),
+                1 => {
+                    let value = *self.possibles[i].iter().next().unwrap();
+                    self.place(i, value);
+                    nplaced += 1;
+                }
+                _ => {}
+            }
+        }
+        Ok(nplaced)
+    }
+
+    fn place_hidden_singles(&mut self) -> Result<u32, Contradiction> {
+        let mut nplaced = 0;
+        let geometry = self.board.geometry.clone();
+        for (h, house) in geometry.houses().iter().enumerate() {
+            for value in geometry.digits() {
+                if house
+                    .iter()
+                    .any(|i| self.board.data[*i].value == Some(value))
+                {
+                    continue;
+                }
+                let spots: Vec<usize> = house
+                    .iter()
+                    .cloned()
+                    .filter(|i| self.possibles[*i].contains(&value))
+                    .collect();
+                match spots.len() {
+                    0 => return Err(Contradiction::NoPlace { house: h, value }),
+                    1 => {
+                        self.place(spots[0], value);
+                        nplaced += 1;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(nplaced)
+    }
+
+    /// Prune the candidates of killer cages and variant constraints until neither removes any
+    /// more, as `propagate` does between singles. Returns the number of candidates removed.
+    pub(crate) fn prune_variants(&mut self) -> Result<u32, Contradiction> {
+        let mut nremoved = 0;
+        loop {
+            let removed = self.restrict_cages()? + self.prune_constraints()?;
+            if removed == 0 {
+                return Ok(nremoved);
+            }
+            nremoved += removed;
+        }
+    }
+
+    /// Remove the candidates of cage cells that fit no combination of different digits making up
+    /// the cage's sum. Returns the number of candidates removed.
+    fn restrict_cages(&mut self) -> Result<u32, Contradiction> {
+        let mut nremoved = 0;
+        let geometry = self.board.geometry.clone();
+        for (c, cage) in geometry.cages().iter().enumerate() {
+            let empty: Vec<usize> = cage
+                .cells
+                .iter()
+                .cloned()
+                .filter(|i| self.board.data[*i].value.is_none())
+                .collect();
+            let masks: Vec<u32> = empty
+                .iter()
+                .map(|i| {
+                    self.possibles[*i]
+                        .iter()
+                        .fold(0, |acc, v| acc | 1 << v.get())
+                })
+                .collect();
+            let fitting = cage
+                .fitting_candidates(&self.board, &masks)
+                .ok_or(Contradiction::NoCombination { cage: c })?;
+            for (i, fits) in empty.iter().zip(fitting) {
+                let before = self.possibles[*i].len();
+                self.possibles[*i].retain(|v| fits & 1 << v.get() != 0);
+                nremoved += (before - self.possibles[*i].len()) as u32;
+            }
+        }
+        Ok(nremoved)
+    }
+
+    /// Let every variant constraint remove the candidates it rules out.
+    /// Returns the number of candidates removed.
+    fn prune_constraints(&mut self) -> Result<u32, Contradiction> {
+        let mut nremoved = 0;
+        let geometry = self.board.geometry.clone();
+        for (c, constraint) in geometry.constraints().iter().enumerate() {
+            let cells = constraint.cells();
+            if cells.iter().all(|i| self.board.data[*i].value.is_some()) {
+                if !constraint.is_satisfied(&self.board) {
+                    return Err(Contradiction::Broken { constraint: c });
+                }
+                continue;
+            }
+            nremoved += constraint.prune(&self.board, &mut self.possibles);
+        }
+        Ok(nremoved)
+    }
+}
+
+impl Display for Candidates {
+    /// Like a `Board`, but with the candidates of every empty cell in place of `_`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let geometry = self.board.geometry();
+        let size = geometry.size();
+        let (height, width) = geometry.picture_shape();
+        for row in 0..height {
+            for col in 0..width {
+                let cell: String = match geometry.cell_at(row, col) {
+                    None => ".".to_owned(),
+                    Some(i) => match self.board.data[i].value {
+                        Some(value) => geometry.symbol(value).to_string(),
+                        None => {
+                            let mut values: Vec<_> = self.possibles[i].iter().collect();
+                            values.sort();
+                            values.iter().map(|v| geometry.symbol(**v)).collect()
+                        }
+                    },
+                };
+                write!(f, "{:<width$},", cell, width = size)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU8;
+
+    use crate::board::{parse_board_list, Board};
+
+    use super::{Candidates, Contradiction};
+
+    #[test]
+    fn test_propagate_solves_easy_board() {
+        let mut cands = Candidates::new(
+            Board::from_str(
+                "_,_,4,1,_,_,5,2,7
+                2,1,3,7,_,_,_,_,_
+                _,_,7,6,2,4,_,_,_
+                3,5,_,2,7,_,_,_,_
+                _,_,_,_,3,_,8,7,5
+                _,4,_,_,_,6,_,1,3
+                4,7,2,_,1,_,_,5,_
+                _,3,1,_,6,2,_,_,9
+                9,_,_,_,_,_,1,8,_",
+            )
+            .unwrap(),
+        );
+        cands.propagate().unwrap();
+        assert!(cands.board().is_solved());
+    }
+
+    #[test]
+    fn test_propagate_finds_contradiction() {
+        // the last cell of the first row can only be 9, but column 8 already has a 9
+        let mut cands = Candidates::new(
+            Board::from_str(
+                "1,2,3,4,5,6,7,8,_
+                _,_,_,_,_,_,_,_,9
+                _,_,_,_,_,_,_,_,_
+                _,_,_,_,_,_,_,_,_
+                _,_,_,_,_,_,_,_,_
+                _,_,_,_,_,_,_,_,_
+                _,_,_,_,_,_,_,_,_
+                _,_,_,_,_,_,_,_,_
+                _,_,_,_,_,_,_,_,_",
+            )
+            .unwrap(),
+        );
+        assert_eq!(
+            Err(Contradiction::NoCandidates { cell: 8 }),
+            cands.propagate()
+        );
+    }
+
+    #[test]
+    fn test_propagate_killer_cages() {
+        let killer = "K1,Web,unsolved,killer
+            _,_,_,_
+            _,_,_,_
+            _,_,_,_
+            _,_,_,_
+            4: r1c1 r2c1
+            5: r1c2 r1c3
+            6: r1c4 r2c4
+            9: r2c2 r2c3 r3c3
+            9: r3c1 r4c1 r4c2
+            1: r3c2
+            4: r3c4 r4c4
+            2: r4c3";
+        let board = parse_board_list(&mut killer.as_bytes()).unwrap()[0]
+            .board
+            .clone();
+
+        // 4 in two cells can only be 1 and 3, and 6 only 2 and 4
+        let cands = Candidates::new(board.clone());
+        let mut first: Vec<u8> = cands.get(0).iter().map(|v| v.get()).collect();
+        first.sort();
+        assert_eq!(vec![1, 3], first);
+        assert_eq!(2, cands.get(3).len());
+
+        let mut cands = Candidates::new(board.clone());
+        cands.propagate().unwrap();
+        assert!(cands.board().is_solved());
+
+        // 2 and 1 in the cage of 5
+        let mut wrong = board;
+        wrong.data[1].value = NonZeroU8::new(2);
+        wrong.data[2].value = NonZeroU8::new(1);
+        assert_eq!(
+            Err(Contradiction::NoCombination { cage: 1 }),
+            Candidates::new(wrong).restrict_cages()
+        );
+    }
+}
@@ -0,0 +1,423 @@
+use std::num::NonZeroU8;
+
+use crate::{
+    board::{
+        geometry::{ChessMove, Geometry},
+        restriction::Restriction,
+        Board,
+    },
+    candidates::{Candidates, Contradiction},
+    logic::{find_step, CellValue, SolveStep, Technique},
+    naive::constraint_possibles,
+};
+
+/// A reason a digit cannot go in a cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Exclusion {
+    /// The cell already holds another digit
+    Filled { value: NonZeroU8 },
+    /// `peer` already holds the digit, and shares `house` with the cell
+    Peer { peer: usize, house: usize },
+    /// `peer` already holds the digit, and is a `rule` move away from the cell
+    ChessMove { peer: usize, rule: ChessMove },
+    /// The digit would repeat in the cell's `cage`, or leave it unable to reach its sum
+    Cage { cage: usize },
+    /// A restriction rules the digit out, such as a consecutive digit next to the cell
+    Restriction { restriction: Restriction },
+    /// A variant constraint, given as an index into `Geometry::constraints`, rules the digit out
+    Constraint { constraint: usize },
+    /// A logical step removed the digit from the cell, either directly,
+    /// by placing it in a peer, or by placing another digit in the cell
+    Deduction { step: SolveStep },
+}
+
+impl Exclusion {
+    /// The reason as text, naming cells and houses as laid out by `geometry`
+    pub fn describe(&self, geometry: &Geometry) -> String {
+        match self {
+            Exclusion::Filled { value } => {
+                format!("the cell already holds {}", geometry.symbol(*value))
+            }
+            Exclusion::Peer { peer, house } => format!(
+                "{} in the same {} already holds it",
+                geometry.cell_name(*peer),
+                geometry.house_name(*house)
+            ),
+            Exclusion::ChessMove { peer, rule } => format!(
+                "{} already holds it, and {} rules it out",
+                geometry.cell_name(*peer),
+                rule.name()
+            ),
+            Exclusion::Cage { cage } => format!(
+                "{} cannot take it without repeating a digit or missing its sum",
+                geometry.cage_name(*cage)
+            ),
+            Exclusion::Restriction { restriction } => match restriction {
+                Restriction::NonConsecutive => {
+                    "a cell next to it holds a consecutive digit, and non-consecutive rules it out"
+                        .to_owned()
+                }
+                Restriction::Even(_) => "the cell is shaded even".to_owned(),
+                Restriction::Odd(_) => "the cell is shaded odd".to_owned(),
+            },
+            Exclusion::Constraint { constraint } => format!(
+                "the {} rules it out",
+                geometry.constraints()[*constraint].describe(geometry)
+            ),
+            Exclusion::Deduction { step } => step.describe(geometry),
+        }
+    }
+}
+
+/// Explain why `value` cannot go in the cell at `index`.
+///
+/// Peers holding `value` are listed first, then the cell's killer cage, restrictions or variant
+/// constraints if they rule it out. If none does, logic is run from the board until some step
+/// rules `value` out of the cell. An empty list means the board does not rule it out, as far as
+/// the known techniques can tell.
+pub fn why_not(board: &Board, index: usize, value: NonZeroU8) -> Vec<Exclusion> {
+    match board.data[index].value {
+        Some(v) if v == value => return vec![],
+        Some(v) => return vec![Exclusion::Filled { value: v }],
+        None => {}
+    }
+
+    let geometry = board.geometry();
+    let peers: Vec<Exclusion> = geometry
+        .neighbors(index)
+        .iter()
+        .cloned()
+        .filter(|n| board.data[*n].value == Some(value))
+        .flat_map(|peer| match geometry.shared_house(index, peer) {
+            Some(house) => Some(Exclusion::Peer { peer, house }),
+            None => geometry
+                .chess_move_between(index, peer)
+                .map(|rule| Exclusion::ChessMove { peer, rule }),
+        })
+        .collect();
+    if !peers.is_empty() {
+        return peers;
+    }
+    if let Some(cage) = geometry.cage_of(index) {
+        if geometry.cages()[cage].open_digits(board) & 1 << value.get() == 0 {
+            return vec![Exclusion::Cage { cage }];
+        }
+    }
+    let restrictions: Vec<Exclusion> = geometry
+        .restrictions()
+        .iter()
+        .filter(|r| !r.allows(board, index, value))
+        .map(|restriction| Exclusion::Restriction {
+            restriction: *restriction,
+        })
+        .collect();
+    if !restrictions.is_empty() {
+        return restrictions;
+    }
+    let constraints: Vec<Exclusion> = geometry
+        .constraints_of(index)
+        .iter()
+        .cloned()
+        .filter(|c| !constraint_possibles(board, index, *c).contains(&value))
+        .map(|constraint| Exclusion::Constraint { constraint })
+        .collect();
+    if !constraints.is_empty() {
+        return constraints;
+    }
+
+    let mut cands = Candidates::new(board.clone());
+    while cands.get(index).contains(&value) {
+        let step = match find_step(&cands, &Technique::ALL) {
+            Some(step) => step,
+            None => break,
+        };
+        step.apply(&mut cands);
+        if cands.board().data[index].value == Some(value) {
+            break;
+        }
+        if !cands.get(index).contains(&value) {
+            return vec![Exclusion::Deduction { step }];
+        }
+    }
+    vec![]
+}
+
+/// What follows from assuming a digit in a cell
+#[derive(Debug, Clone)]
+pub struct WhatIf {
+    /// Digits placed by propagation, not counting the assumed one
+    pub placements: Vec<CellValue>,
+    /// Candidates removed from cells that are still empty
+    pub eliminations: Vec<CellValue>,
+    /// Where propagation ran into a contradiction, if it did
+    pub contradiction: Option<Contradiction>,
+    /// The board and candidates after propagation, or at the point of contradiction
+    pub end: Candidates,
+}
+
+/// Place `value` in the cell at `index` on a copy of the board, and propagate singles from there
+pub fn what_if(board: &Board, index: usize, value: NonZeroU8) -> WhatIf {
+    what_if_for(&Candidates::new(board.clone()), index, value)
+}
+
+/// Like `what_if`, for a board whose candidates have already been narrowed down.
+/// Assuming the digit a cell already holds changes nothing. Assuming another digit in a filled
+/// cell, or a digit that is no longer a candidate, is a contradiction right away,
+/// and nothing is placed.
+pub fn what_if_for(cands: &Candidates, index: usize, value: NonZeroU8) -> WhatIf {
+    let mut end = cands.clone();
+    let peer = cands
+        .board()
+        .geometry()
+        .neighbors(index)
+        .iter()
+        .cloned()
+        .find(|n| cands.board().data[*n].value == Some(value));
+    let contradiction = match cands.board().data[index].value {
+        Some(v) if v == value => None,
+        Some(_) => Some(Contradiction::Filled { cell: index }),
+        None => match peer {
+            Some(peer) => Some(Contradiction::Conflict { cell: index, peer }),
+            None if !cands.get(index).contains(&value) => {
+                Some(Contradiction::NotCandidate { cell: index, value })
+            }
+            None => {
+                end.place(index, value);
+                end.propagate().err()
+            }
+        },
+    };
+
+    let mut placements = vec![];
+    let mut eliminations = vec![];
+    for i in
+        (0..cands.possibles.len()).filter(|i| *i != index && cands.board().data[*i].value.is_none())
+    {
+        match end.board().data[i].value {
+            Some(v) => placements.push((i, v)),
+            None => {
+                let mut removed: Vec<_> = cands.get(i).difference(end.get(i)).cloned().collect();
+                removed.sort();
+                eliminations.extend(removed.into_iter().map(|v| (i, v)));
+            }
+        }
+    }
+
+    WhatIf {
+        placements,
+        eliminations,
+        contradiction,
+        end,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU8;
+
+    use std::sync::Arc;
+
+    use crate::{
+        board::{
+            constraint::parse_constraint,
+            geometry::{ChessMove, Geometry},
+            parse_board_list,
+            restriction::Restriction,
+            Board,
+        },
+        candidates::{Candidates, Contradiction},
+    };
+
+    use super::{what_if, what_if_for, why_not, Exclusion};
+
+    fn board() -> Board {
+        Board::from_str(
+            "_,_,4,1,_,_,5,2,7
+            2,1,3,7,_,_,_,_,_
+            _,_,7,6,2,4,_,_,_
+            3,5,_,2,7,_,_,_,_
+            _,_,_,_,3,_,8,7,5
+            _,4,_,_,_,6,_,1,3
+            4,7,2,_,1,_,_,5,_
+            _,3,1,_,6,2,_,_,9
+            9,_,_,_,_,_,1,8,_",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_peers() {
+        // r1c3 shares row 1 (and box 1) with r1c1, and r7c1 shares column 1
+        assert_eq!(
+            vec![
+                Exclusion::Peer { peer: 2, house: 0 },
+                Exclusion::Peer { peer: 54, house: 9 }
+            ],
+            why_not(&board(), 0, NonZeroU8::new(4).unwrap())
+        );
+        assert_eq!(
+            vec![Exclusion::Filled {
+                value: NonZeroU8::new(4).unwrap()
+            }],
+            why_not(&board(), 2, NonZeroU8::new(1).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_cage() {
+        let killer = "K1,Web,unsolved,killer
+            _,_,_,_
+            _,_,_,_
+            _,_,_,_
+            _,_,_,_
+            4: r1c1 r2c1
+            5: r1c2 r1c3
+            6: r1c4 r2c4
+            9: r2c2 r2c3 r3c3
+            9: r3c1 r4c1 r4c2
+            1: r3c2
+            4: r3c4 r4c4
+            2: r4c3";
+        let board = &parse_board_list(&mut killer.as_bytes()).unwrap()[0].board;
+        // 2 and 2 would make 4, but may not repeat
+        let reasons = why_not(board, 0, NonZeroU8::new(2).unwrap());
+        assert_eq!(vec![Exclusion::Cage { cage: 0 }], reasons);
+        assert_eq!(
+            "cage 1 (4) cannot take it without repeating a digit or missing its sum",
+            reasons[0].describe(board.geometry())
+        );
+        assert!(why_not(board, 0, NonZeroU8::new(3).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_chess_move() {
+        let mut board = Board::empty(Arc::new(
+            Geometry::standard().with_chess_move(ChessMove::Knight),
+        ));
+        board.data[40].value = NonZeroU8::new(5);
+        let reasons = why_not(&board, 29, NonZeroU8::new(5).unwrap());
+        assert_eq!(
+            vec![Exclusion::ChessMove {
+                peer: 40,
+                rule: ChessMove::Knight
+            }],
+            reasons
+        );
+        assert_eq!(
+            "r5c5 already holds it, and anti-knight rules it out",
+            reasons[0].describe(board.geometry())
+        );
+    }
+
+    #[test]
+    fn test_constraint() {
+        let thermo = parse_constraint("thermo: r1c1 r1c2 r1c3", &Geometry::standard()).unwrap();
+        let board = Board::empty(Arc::new(
+            Geometry::standard().with_constraint(thermo).unwrap(),
+        ));
+        // two digits must fit above the bulb
+        let reasons = why_not(&board, 0, NonZeroU8::new(8).unwrap());
+        assert_eq!(vec![Exclusion::Constraint { constraint: 0 }], reasons);
+        assert_eq!(
+            "the thermometer r1c1 r1c2 r1c3 rules it out",
+            reasons[0].describe(board.geometry())
+        );
+        assert!(why_not(&board, 0, NonZeroU8::new(7).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_restriction() {
+        let geometry = Geometry::standard()
+            .with_restriction(Restriction::NonConsecutive)
+            .unwrap()
+            .with_restriction(Restriction::Odd(1))
+            .unwrap();
+        let mut board = Board::empty(Arc::new(geometry));
+        board.data[0].value = NonZeroU8::new(5);
+        // r1c2 is shaded odd, and next to the 5 in r1c1
+        assert_eq!(
+            vec![
+                Exclusion::Restriction {
+                    restriction: Restriction::NonConsecutive
+                },
+                Exclusion::Restriction {
+                    restriction: Restriction::Odd(1)
+                },
+            ],
+            why_not(&board, 1, NonZeroU8::new(4).unwrap())
+        );
+        assert_eq!(
+            "the cell is shaded odd",
+            why_not(&board, 1, NonZeroU8::new(8).unwrap())[0].describe(board.geometry())
+        );
+        assert!(why_not(&board, 1, NonZeroU8::new(7).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_deduction() {
+        // r1c1 is 6 in the solution, and no peer holds 8 yet
+        let reasons = why_not(&board(), 0, NonZeroU8::new(8).unwrap());
+        assert_eq!(1, reasons.len());
+        assert!(matches!(reasons[0], Exclusion::Deduction { .. }));
+
+        assert!(why_not(&board(), 0, NonZeroU8::new(6).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_what_if_solution_digit() {
+        // 6 is right for r1c1, and singles take it from there
+        let res = what_if(&board(), 0, NonZeroU8::new(6).unwrap());
+        assert_eq!(None, res.contradiction);
+        assert!(res.end.board().is_solved());
+        assert_eq!(
+            board().data.iter().filter(|t| t.value.is_none()).count() - 1,
+            res.placements.len()
+        );
+    }
+
+    #[test]
+    fn test_what_if_contradiction() {
+        let res = what_if(&board(), 0, NonZeroU8::new(4).unwrap());
+        assert_eq!(
+            Some(Contradiction::Conflict { cell: 0, peer: 2 }),
+            res.contradiction
+        );
+
+        // 8 has no peer in r1c1, but is wrong; propagation runs into trouble somewhere
+        let res = what_if(&board(), 0, NonZeroU8::new(8).unwrap());
+        assert!(res.contradiction.is_some());
+        assert!(!res.placements.is_empty() || !res.eliminations.is_empty());
+    }
+
+    #[test]
+    fn test_what_if_filled_cell() {
+        // r1c3 already holds 4; nothing is placed over it
+        let res = what_if(&board(), 2, NonZeroU8::new(6).unwrap());
+        assert_eq!(Some(Contradiction::Filled { cell: 2 }), res.contradiction);
+        assert_eq!(board(), *res.end.board());
+        assert!(res.placements.is_empty() && res.eliminations.is_empty());
+
+        // assuming the 4 it holds changes nothing
+        let res = what_if(&board(), 2, NonZeroU8::new(4).unwrap());
+        assert_eq!(None, res.contradiction);
+        assert_eq!(board(), *res.end.board());
+        assert!(res.placements.is_empty() && res.eliminations.is_empty());
+    }
+
+    #[test]
+    fn test_what_if_not_a_candidate() {
+        let mut cands = Candidates::new(board());
+        assert!(cands.eliminate(0, NonZeroU8::new(6).unwrap()));
+        // 6 has no peer in r1c1, but is no longer among its candidates
+        let res = what_if_for(&cands, 0, NonZeroU8::new(6).unwrap());
+        assert_eq!(
+            Some(Contradiction::NotCandidate {
+                cell: 0,
+                value: NonZeroU8::new(6).unwrap()
+            }),
+            res.contradiction
+        );
+        assert_eq!(None, res.end.board().data[0].value);
+        assert!(res.placements.is_empty() && res.eliminations.is_empty());
+    }
+}
@@ -40,6 +40,7 @@ impl FilledBoard {
     }
 
     /// Parse a Board from a &str representation
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(repr: &str) -> Result<Self, String> {
         Ok(Self {
             data: repr
@@ -48,21 +49,231 @@ impl FilledBoard {
                 .map(|c| c.to_digit(10).unwrap() as u8)
                 .collect::<Vec<u8>>()
                 .try_into()
-                .or_else(|e: Vec<_>| {
-                    Err(format!(
-                        "Expected {} numbers in board, got {}",
-                        N_CELLS,
-                        e.len()
-                    ))
+                .map_err(|e: Vec<_>| {
+                    format!("Expected {} numbers in board, got {}", N_CELLS, e.len())
                 })?,
         })
     }
 }
 
+/// Proposals `FilledBoard::random` draws after its first one before it settles on a grid
+const MIXING_STEPS: usize = 32;
+
+impl FilledBoard {
+    /// A uniformly random solved grid, reproducible from `seed`.
+    ///
+    /// Filling in the cells with random digits favors some grids over others, so such fills are
+    /// only proposals to a Metropolis-Hastings chain whose stationary distribution is uniform over
+    /// all solved grids. A proposal fills the cells in order, each with a digit picked evenly from
+    /// those that still leave the grid solvable, so it comes up with probability 1 / W, where W
+    /// multiplies the numbers of digits there were to pick from. The chain moves to a proposal
+    /// with probability min(1, W' / W). Any grid can be proposed, so the chain forgets where it
+    /// started at a geometric rate; after `MIXING_STEPS` proposals, the bias left is too small to
+    /// show in the weights of thousands of grids.
+    pub fn random(seed: u64) -> Self {
+        let mut rng = SplitMix64(seed);
+        let (mut grid, mut weight) = propose(&mut rng);
+        for _ in 0..MIXING_STEPS {
+            let (next, next_weight) = propose(&mut rng);
+            if rng.unit() * weight < next_weight {
+                grid = next;
+                weight = next_weight;
+            }
+        }
+        Self { data: grid.data }
+    }
+}
+
+/// Fill a grid in cell order, picking each digit evenly from those that leave the rest solvable.
+/// Returns it with its weight: the product of the numbers of digits there were to pick from.
+fn propose(rng: &mut SplitMix64) -> (PartialGrid, f64) {
+    let mut grid = PartialGrid::default();
+    // a solution agreeing with `grid` so far, which vouches for one digit of the next cell
+    let mut witness = grid;
+    witness.fill();
+    let mut weight = 1.0;
+    for i in 0..N_CELLS {
+        let mut options = vec![];
+        // a swap in a solution already found vouches for a digit much faster than a search
+        for d in grid.fitting(i) {
+            if d == witness.data[i] {
+                options.push(witness);
+            } else if let Some(swapped) = std::iter::once(&witness)
+                .chain(options.iter())
+                .find_map(|g| g.swapped(i, d))
+            {
+                options.push(swapped);
+            } else {
+                let mut trial = grid;
+                trial.set(i, d);
+                if trial.fill() {
+                    options.push(trial);
+                }
+            }
+        }
+        weight *= options.len() as f64;
+        witness = options[rng.below(options.len())];
+        grid.set(i, witness.data[i]);
+    }
+    (grid, weight)
+}
+
+/// A grid being filled in, with the digits used in each row, column and box as bit masks
+#[derive(Clone, Copy)]
+struct PartialGrid {
+    data: [u8; N_CELLS],
+    used: [u16; 27],
+}
+
+impl Default for PartialGrid {
+    fn default() -> Self {
+        Self {
+            data: [0; N_CELLS],
+            used: [0; 27],
+        }
+    }
+}
+
+impl PartialGrid {
+    /// Row, column and box of a cell, as indices into `used`
+    fn houses(index: usize) -> [usize; 3] {
+        [
+            index / 9,
+            9 + index % 9,
+            18 + index / 27 * 3 + index % 9 / 3,
+        ]
+    }
+
+    fn fitting_mask(&self, index: usize) -> u16 {
+        let [row, col, block] = Self::houses(index);
+        0x3fe & !(self.used[row] | self.used[col] | self.used[block])
+    }
+
+    /// Digits the houses of an empty cell leave for it, low to high
+    fn fitting(&self, index: usize) -> impl Iterator<Item = u8> {
+        let mask = self.fitting_mask(index);
+        (1..=9).filter(move |d| mask & 1 << d != 0)
+    }
+
+    fn set(&mut self, index: usize, digit: u8) {
+        self.data[index] = digit;
+        for h in Self::houses(index) {
+            self.used[h] |= 1 << digit;
+        }
+    }
+
+    fn clear(&mut self, index: usize) {
+        for h in Self::houses(index) {
+            self.used[h] &= !(1 << self.data[index]);
+        }
+        self.data[index] = 0;
+    }
+
+    /// This filled grid with `digit` in the cell at `index`, found by swapping it with the digit
+    /// there in all the cells linked to `index` through houses holding both. Every house holds one
+    /// of each, and still does after the swap. `None` if that would change a cell before `index`.
+    fn swapped(&self, index: usize, digit: u8) -> Option<Self> {
+        let other = self.data[index];
+        let mut linked = vec![index];
+        let mut k = 0;
+        while k < linked.len() {
+            let cell = linked[k];
+            let wanted = if self.data[cell] == other {
+                digit
+            } else {
+                other
+            };
+            for h in Self::houses(cell) {
+                for c in HOUSES[h] {
+                    if self.data[c] != wanted || linked.contains(&c) {
+                        continue;
+                    }
+                    if c < index {
+                        return None;
+                    }
+                    linked.push(c);
+                }
+            }
+            k += 1;
+        }
+        // the houses keep the same digits, so `used` stays as it is
+        let mut grid = *self;
+        for c in linked {
+            grid.data[c] = if grid.data[c] == other { digit } else { other };
+        }
+        Some(grid)
+    }
+
+    /// Fill in the empty cells, always the one with the fewest digits left first.
+    /// Returns `false`, leaving the grid as it was, if there is no way to.
+    fn fill(&mut self) -> bool {
+        let mut empty: Vec<usize> = (0..N_CELLS).filter(|i| self.data[*i] == 0).collect();
+        self.fill_cells(&mut empty)
+    }
+
+    /// `fill` for the cells in `empty`, which it reorders
+    fn fill_cells(&mut self, empty: &mut [usize]) -> bool {
+        if empty.is_empty() {
+            return true;
+        }
+        let mut best = 0;
+        let mut fewest = u32::MAX;
+        for (k, cell) in empty.iter().enumerate() {
+            let count = self.fitting_mask(*cell).count_ones();
+            if count < fewest {
+                best = k;
+                fewest = count;
+                if count <= 1 {
+                    break;
+                }
+            }
+        }
+        empty.swap(0, best);
+        let index = empty[0];
+        for d in self.fitting(index) {
+            self.set(index, d);
+            if self.fill_cells(&mut empty[1..]) {
+                return true;
+            }
+            self.clear(index);
+        }
+        false
+    }
+}
+
+/// The SplitMix64 generator: small, and the same on every platform, so a seed always gives
+/// the same grid
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number below `n`; the bias of taking the remainder is too small to matter for small `n`
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    /// A number in [0, 1), from the top 53 bits so that every value is exact
+    fn unit(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
 impl FilledBoard {
     /// Check if board is correctly solved
     pub fn is_solved(&self) -> bool {
-        HOUSES.iter().all(|rule| self.is_valid_on_rule(rule))
+        self.is_solved_with(&HOUSES)
+    }
+
+    /// Check if board is correctly solved against `houses`, such as `HOUSES` plus the diagonals of Sudoku-X
+    pub fn is_solved_with(&self, houses: &[House]) -> bool {
+        houses.iter().all(|rule| self.is_valid_on_rule(rule))
     }
 
     /// Checks that the board is valid against a certain `Rule`, such as for a row, with rule indeces [0, 1, 2, 3, 4, 5, 6, 7, 8]
@@ -95,6 +306,7 @@ mod tests {
     pub type HouseGroup = [House; 9];
 
     use super::*;
+    use crate::board::positions::{DISJOINT_GROUPS, N_LINES};
 
     /// Rule for a block, in horizontal order
     const BLOCKS: HouseGroup = [
@@ -232,6 +444,44 @@ B3-1,UGLY!,incorrect
         Ok(())
     }
 
+    #[test]
+    fn test_random() {
+        for seed in 0..20 {
+            let filled = FilledBoard::random(seed);
+            assert!(filled.is_solved());
+            assert_eq!(filled, FilledBoard::random(seed));
+        }
+        assert_ne!(FilledBoard::random(7), FilledBoard::random(8));
+    }
+
+    #[test]
+    fn test_other_house_sets() {
+        // each row is the one above shifted by one: a Latin square, but the boxes repeat digits
+        let mut data = [0; N_CELLS];
+        for (i, cell) in data.iter_mut().enumerate() {
+            *cell = ((i / 9 + i % 9) % 9 + 1) as u8;
+        }
+        let latin = FilledBoard { data };
+        assert!(latin.is_solved_with(&HOUSES[..N_LINES]));
+        assert!(!latin.is_solved());
+
+        let disjoint = FilledBoard::from_str(
+            "2,1,5,4,3,8,6,7,9
+            3,6,9,2,7,1,8,4,5
+            4,7,8,6,9,5,3,2,1
+            8,2,4,9,6,7,1,5,3
+            1,3,6,5,2,4,9,8,7
+            9,5,7,8,1,3,2,6,4
+            7,4,1,3,8,6,5,9,2
+            6,9,3,7,5,2,4,1,8
+            5,8,2,1,4,9,7,3,6",
+        )
+        .unwrap();
+        let houses: Vec<House> = HOUSES.iter().chain(DISJOINT_GROUPS.iter()).cloned().collect();
+        assert!(disjoint.is_solved_with(&houses));
+        assert!(!SWAPPED_BOARD0.is_solved_with(&DISJOINT_GROUPS));
+    }
+
     #[test]
     fn valid_rule_group() {
         let b = SWAPPED_BOARD0;
@@ -0,0 +1,184 @@
+use std::{cmp::Reverse, fmt::Debug, num::NonZeroU8};
+
+use fnv::FnvHashSet;
+
+use crate::{board::Board, naive::find_possibles};
+
+/// Chooses which empty cell the search branches on next
+pub trait CellSelector: Debug {
+    /// Index of the chosen cell, or `None` if the board is filled
+    fn select(&self, board: &Board) -> Option<usize>;
+}
+
+/// Chooses the order in which the digits of a cell are tried
+pub trait ValueOrder: Debug {
+    fn order(
+        &self,
+        board: &Board,
+        index: usize,
+        possibles: &FnvHashSet<NonZeroU8>,
+    ) -> Vec<NonZeroU8>;
+}
+
+fn empty_cells(board: &Board) -> impl Iterator<Item = usize> + '_ {
+    board
+        .data
+        .iter()
+        .enumerate()
+        .filter(|(_, tile)| tile.value.is_none())
+        .map(|(i, _)| i)
+}
+
+/// The first empty cell in reading order
+#[derive(Debug, Clone, Copy)]
+pub struct FirstEmpty;
+
+impl CellSelector for FirstEmpty {
+    fn select(&self, board: &Board) -> Option<usize> {
+        empty_cells(board).next()
+    }
+}
+
+/// The empty cell with the fewest candidates (ties go to the first one)
+#[derive(Debug, Clone, Copy)]
+pub struct MinimumRemainingValues;
+
+impl CellSelector for MinimumRemainingValues {
+    fn select(&self, board: &Board) -> Option<usize> {
+        empty_cells(board).min_by_key(|i| find_possibles(board, *i).len())
+    }
+}
+
+/// Among the empty cells with the fewest candidates, the one with the most empty neighbors,
+/// so a placement there constrains the most cells (ties go to the first one)
+#[derive(Debug, Clone, Copy)]
+pub struct MostConstrainingCell;
+
+impl CellSelector for MostConstrainingCell {
+    fn select(&self, board: &Board) -> Option<usize> {
+        empty_cells(board).min_by_key(|i| {
+            (
+                find_possibles(board, *i).len(),
+                Reverse(
+                    board
+                        .geometry
+                        .neighbors(*i)
+                        .iter()
+                        .filter(|n| board.data[**n].value.is_none())
+                        .count(),
+                ),
+            )
+        })
+    }
+}
+
+/// Digits in whatever order `find_possibles` yields them
+#[derive(Debug, Clone, Copy)]
+pub struct AnyOrder;
+
+impl ValueOrder for AnyOrder {
+    fn order(&self, _: &Board, _: usize, possibles: &FnvHashSet<NonZeroU8>) -> Vec<NonZeroU8> {
+        possibles.iter().cloned().collect()
+    }
+}
+
+/// Digits that rule out the fewest candidates of empty neighbors come first
+#[derive(Debug, Clone, Copy)]
+pub struct LeastConstrainingValue;
+
+impl ValueOrder for LeastConstrainingValue {
+    fn order(
+        &self,
+        board: &Board,
+        index: usize,
+        possibles: &FnvHashSet<NonZeroU8>,
+    ) -> Vec<NonZeroU8> {
+        let neighbor_possibles: Vec<_> = board
+            .geometry
+            .neighbors(index)
+            .iter()
+            .cloned()
+            .filter(|n| board.data[*n].value.is_none())
+            .map(|n| find_possibles(board, n))
+            .collect();
+
+        let mut values: Vec<NonZeroU8> = possibles.iter().cloned().collect();
+        values.sort_by_key(|v| {
+            (
+                neighbor_possibles
+                    .iter()
+                    .filter(|ps| ps.contains(v))
+                    .count(),
+                *v,
+            )
+        });
+        values
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU8;
+
+    use crate::{board::Board, naive::find_possibles};
+
+    use super::{
+        CellSelector, FirstEmpty, LeastConstrainingValue, MinimumRemainingValues,
+        MostConstrainingCell, ValueOrder,
+    };
+
+    /// Only the middle row is given, and it is missing its last digit
+    fn board() -> Board {
+        Board::from_str(
+            "_,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            1,2,3,4,5,6,7,8,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cell_selectors() {
+        let b = board();
+        assert_eq!(Some(0), FirstEmpty.select(&b));
+        // the end of the middle row can only be 9
+        assert_eq!(Some(44), MinimumRemainingValues.select(&b));
+        assert_eq!(Some(44), MostConstrainingCell.select(&b));
+
+        // r5c8 and r5c9 can both be 8 or 9, but the 1 above r5c8 leaves it fewer empty neighbors
+        let tied = Board::from_str(
+            "_,_,_,_,_,_,_,1,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            1,2,3,4,5,6,7,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,_,_",
+        )
+        .unwrap();
+        assert_eq!(Some(43), MinimumRemainingValues.select(&tied));
+        assert_eq!(Some(44), MostConstrainingCell.select(&tied));
+    }
+
+    #[test]
+    fn test_least_constraining_value() {
+        let b = board();
+        let possibles = find_possibles(&b, 0);
+        let order = LeastConstrainingValue.order(&b, 0, &possibles);
+        assert_eq!(possibles.len(), order.len());
+        assert!(order.iter().all(|v| possibles.contains(v)));
+        // 2 and 3 are already ruled out of the most neighbors (by their columns and the middle-left box)
+        assert_eq!(
+            vec![NonZeroU8::new(2).unwrap(), NonZeroU8::new(3).unwrap()],
+            order[..2].to_vec()
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+
+use crate::{
+    board::{geometry::Geometry, Board},
+    candidates::Candidates,
+    logic::{find_step, SolveStep, Technique},
+};
+
+/// How much a hint gives away
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HintLevel {
+    /// Which house to look at
+    House,
+    /// Which technique to use there
+    Technique,
+    /// The exact placement or elimination
+    Step,
+}
+
+/// The easiest logical step available on a board, to be revealed a level at a time
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hint {
+    pub step: SolveStep,
+    geometry: Arc<Geometry>,
+}
+
+impl Hint {
+    /// Houses to look at, as indices into the board's `Geometry::houses`
+    pub fn houses(&self) -> Vec<usize> {
+        self.step.houses(&self.geometry)
+    }
+
+    pub fn technique(&self) -> Technique {
+        self.step.technique()
+    }
+
+    /// The hint as text, giving away no more than `level`
+    pub fn describe(&self, level: HintLevel) -> String {
+        let houses = self
+            .houses()
+            .iter()
+            .map(|h| self.geometry.house_name(*h))
+            .collect::<Vec<_>>()
+            .join(" and ");
+        match level {
+            HintLevel::House => format!("Look at {}", houses),
+            HintLevel::Technique => format!("Look at {} ({})", houses, self.technique()),
+            HintLevel::Step => self.step.describe(&self.geometry),
+        }
+    }
+}
+
+/// Hint for a board, with candidates worked out from the digits on it.
+/// If the easiest step only eliminates candidates, the board does not change by following it,
+/// so players who keep pencil marks should use `hint_for` instead.
+pub fn hint(board: &Board) -> Option<Hint> {
+    hint_for(&Candidates::new(board.clone()))
+}
+
+/// Hint for a board whose candidates have already been narrowed down
+pub fn hint_for(cands: &Candidates) -> Option<Hint> {
+    if cands.board().is_filled() {
+        return None;
+    }
+    find_step(cands, &Technique::ALL).map(|step| Hint {
+        step,
+        geometry: cands.board().geometry.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{num::NonZeroU8, sync::Arc};
+
+    use crate::{
+        board::{geometry::Geometry, Board},
+        candidates::Candidates,
+        logic::{SolveStep, Technique},
+    };
+
+    use super::{hint, hint_for, Hint, HintLevel};
+
+    fn board() -> Board {
+        Board::from_str(
+            "_,_,6,_,9,_,_,_,_
+            1,7,_,_,_,3,_,9,_
+            _,_,_,7,_,_,_,_,5
+            _,_,_,5,_,_,6,_,_
+            _,9,_,_,3,_,2,_,_
+            _,_,4,_,_,2,1,_,_
+            _,_,_,9,7,8,_,_,_
+            _,4,_,_,_,5,_,8,_
+            _,_,_,_,_,6,_,_,_",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_hint_levels() {
+        let h = hint(&board()).unwrap();
+        assert_eq!(Technique::HiddenSingle, h.technique());
+        assert!(h.describe(HintLevel::House).starts_with("Look at box"));
+        assert!(h.describe(HintLevel::Technique).contains("Hidden single"));
+        assert_eq!(
+            h.step.describe(board().geometry()),
+            h.describe(HintLevel::Step)
+        );
+    }
+
+    #[test]
+    fn test_hint_on_latin_square() {
+        // with no boxes, a naked single points at the cell's row
+        let h = Hint {
+            step: SolveStep::NakedSingle {
+                cell: 5,
+                value: NonZeroU8::new(3).unwrap(),
+            },
+            geometry: Arc::new(Geometry::latin(4)),
+        };
+        assert_eq!("Look at row 2", h.describe(HintLevel::House));
+
+        let latin = Board::from_str_with(
+            "1,2,3,_
+            _,4,_,2
+            2,_,4,_
+            _,3,_,1",
+            Arc::new(Geometry::latin(4)),
+        )
+        .unwrap();
+        let mut cands = Candidates::new(latin);
+        while let Some(h) = hint_for(&cands) {
+            assert!(!h.houses().is_empty(), "{:?}", h.step);
+            h.step.apply(&mut cands);
+        }
+        assert!(cands.board().is_solved());
+    }
+
+    #[test]
+    fn test_hints_lead_to_solution() {
+        let mut cands = Candidates::new(board());
+        while let Some(h) = hint_for(&cands) {
+            h.step.apply(&mut cands);
+        }
+        assert!(cands.board().is_solved());
+        assert_eq!(None, hint(cands.board()));
+    }
+}
@@ -1,10 +1,17 @@
 #![allow(dead_code)]
 
 pub mod board;
+pub mod candidates;
+pub mod explain;
 mod filledboard;
+pub mod heuristics;
+pub mod hint;
+pub mod logic;
 pub mod naive;
+pub mod rating;
 pub mod smart;
 mod swap;
+pub mod template;
 
 #[cfg(feature = "python")]
 use filledboard::FilledBoard;
@@ -18,7 +25,6 @@ use std::process;
 use std::{
     fs::File,
     io::{self, BufRead, BufReader, Write},
-    usize,
 };
 
 #[allow(dead_code)]
@@ -0,0 +1,937 @@
+use std::{
+    fmt::{self, Display},
+    num::NonZeroU8,
+};
+
+use crate::{
+    board::{
+        geometry::{Geometry, HouseKind},
+        Board,
+    },
+    candidates::Candidates,
+};
+
+/// A digit in a cell, as placed or eliminated by a step
+pub type CellValue = (usize, NonZeroU8);
+
+/// A named logical technique
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Technique {
+    HiddenSingle,
+    NakedSingle,
+    Pointing,
+    Claiming,
+    NakedPair,
+    XWing,
+    HiddenPair,
+    NakedTriple,
+    Swordfish,
+    HiddenTriple,
+    XyWing,
+    NakedQuad,
+    HiddenQuad,
+}
+
+impl Technique {
+    /// Hidden and naked singles
+    pub const SINGLES: [Technique; 2] = [Technique::HiddenSingle, Technique::NakedSingle];
+
+    /// Singles, plus naked and hidden subsets of up to four cells
+    pub const SINGLES_AND_SUBSETS: [Technique; 8] = [
+        Technique::HiddenSingle,
+        Technique::NakedSingle,
+        Technique::NakedPair,
+        Technique::HiddenPair,
+        Technique::NakedTriple,
+        Technique::HiddenTriple,
+        Technique::NakedQuad,
+        Technique::HiddenQuad,
+    ];
+
+    /// Every technique, from easiest to hardest
+    pub const ALL: [Technique; 13] = [
+        Technique::HiddenSingle,
+        Technique::NakedSingle,
+        Technique::Pointing,
+        Technique::Claiming,
+        Technique::NakedPair,
+        Technique::XWing,
+        Technique::HiddenPair,
+        Technique::NakedTriple,
+        Technique::Swordfish,
+        Technique::HiddenTriple,
+        Technique::XyWing,
+        Technique::NakedQuad,
+        Technique::HiddenQuad,
+    ];
+}
+
+impl Display for Technique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Technique::HiddenSingle => "Hidden single",
+            Technique::NakedSingle => "Naked single",
+            Technique::Pointing => "Pointing",
+            Technique::Claiming => "Claiming",
+            Technique::NakedPair => "Naked pair",
+            Technique::XWing => "X-Wing",
+            Technique::HiddenPair => "Hidden pair",
+            Technique::NakedTriple => "Naked triple",
+            Technique::Swordfish => "Swordfish",
+            Technique::HiddenTriple => "Hidden triple",
+            Technique::XyWing => "XY-Wing",
+            Technique::NakedQuad => "Naked quad",
+            Technique::HiddenQuad => "Hidden quad",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// One logical deduction. Houses are indices into the board's `Geometry::houses`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveStep {
+    /// `cell` has `value` as its only candidate
+    NakedSingle { cell: usize, value: NonZeroU8 },
+    /// `cell` is the only place left for `value` in `house`
+    HiddenSingle {
+        house: usize,
+        cell: usize,
+        value: NonZeroU8,
+    },
+    /// Within `house`, `value` only fits in `cells`, which all lie in `other` as well,
+    /// so `value` is removed from the rest of `other`.
+    /// Pointing when `house` is a box, claiming when it is a row or column.
+    LockedCandidates {
+        pointing: bool,
+        value: NonZeroU8,
+        house: usize,
+        other: usize,
+        cells: Vec<usize>,
+        eliminations: Vec<CellValue>,
+    },
+    /// `cells` of `house` only hold `values` between them, so those are removed from the rest of `house`
+    NakedSubset {
+        house: usize,
+        cells: Vec<usize>,
+        values: Vec<NonZeroU8>,
+        eliminations: Vec<CellValue>,
+    },
+    /// `values` only fit in `cells` of `house`, so other digits are removed from those cells
+    HiddenSubset {
+        house: usize,
+        cells: Vec<usize>,
+        values: Vec<NonZeroU8>,
+        eliminations: Vec<CellValue>,
+    },
+    /// In the `base` houses, `value` only fits in `cells`, which all lie in the `cover` houses,
+    /// so `value` is removed from the rest of the cover houses (X-Wing, Swordfish)
+    Fish {
+        value: NonZeroU8,
+        base: Vec<usize>,
+        cover: Vec<usize>,
+        cells: Vec<usize>,
+        eliminations: Vec<CellValue>,
+    },
+    /// `pivot` holds xy and sees both `pincers`, which hold xz and yz.
+    /// Either pincer must be z, so z is removed from cells that see both.
+    XyWing {
+        pivot: usize,
+        pincers: [usize; 2],
+        value: NonZeroU8,
+        eliminations: Vec<CellValue>,
+    },
+}
+
+impl SolveStep {
+    pub fn technique(&self) -> Technique {
+        match self {
+            SolveStep::NakedSingle { .. } => Technique::NakedSingle,
+            SolveStep::HiddenSingle { .. } => Technique::HiddenSingle,
+            SolveStep::LockedCandidates { pointing, .. } => {
+                if *pointing {
+                    Technique::Pointing
+                } else {
+                    Technique::Claiming
+                }
+            }
+            SolveStep::NakedSubset { cells, .. } => match cells.len() {
+                2 => Technique::NakedPair,
+                3 => Technique::NakedTriple,
+                _ => Technique::NakedQuad,
+            },
+            SolveStep::HiddenSubset { cells, .. } => match cells.len() {
+                2 => Technique::HiddenPair,
+                3 => Technique::HiddenTriple,
+                _ => Technique::HiddenQuad,
+            },
+            SolveStep::Fish { base, .. } => match base.len() {
+                2 => Technique::XWing,
+                _ => Technique::Swordfish,
+            },
+            SolveStep::XyWing { .. } => Technique::XyWing,
+        }
+    }
+
+    /// Houses where the pattern can be spotted
+    pub fn houses(&self, geometry: &Geometry) -> Vec<usize> {
+        match self {
+            SolveStep::NakedSingle { cell, .. } => {
+                let houses = geometry.houses_of(*cell);
+                // a Latin square has no boxes, so point at the cell's row there
+                houses
+                    .iter()
+                    .cloned()
+                    .find(|h| geometry.kind(*h).is_box())
+                    .or_else(|| houses.first().cloned())
+                    .into_iter()
+                    .collect()
+            }
+            SolveStep::HiddenSingle { house, .. }
+            | SolveStep::LockedCandidates { house, .. }
+            | SolveStep::NakedSubset { house, .. }
+            | SolveStep::HiddenSubset { house, .. } => vec![*house],
+            SolveStep::Fish { base, .. } => base.clone(),
+            SolveStep::XyWing { pivot, pincers, .. } => pincers
+                .iter()
+                .flat_map(|pincer| geometry.shared_house(*pivot, *pincer))
+                .collect(),
+        }
+    }
+
+    /// Cells that make up the pattern
+    pub fn cells(&self) -> Vec<usize> {
+        match self {
+            SolveStep::NakedSingle { cell, .. } | SolveStep::HiddenSingle { cell, .. } => {
+                vec![*cell]
+            }
+            SolveStep::LockedCandidates { cells, .. }
+            | SolveStep::NakedSubset { cells, .. }
+            | SolveStep::HiddenSubset { cells, .. }
+            | SolveStep::Fish { cells, .. } => cells.clone(),
+            SolveStep::XyWing { pivot, pincers, .. } => vec![*pivot, pincers[0], pincers[1]],
+        }
+    }
+
+    /// Digits placed by this step
+    pub fn placements(&self) -> Vec<CellValue> {
+        match self {
+            SolveStep::NakedSingle { cell, value }
+            | SolveStep::HiddenSingle { cell, value, .. } => {
+                vec![(*cell, *value)]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Candidates removed by this step. Placements also remove their digit from every neighbor,
+    /// but those are not listed.
+    pub fn eliminations(&self) -> &[CellValue] {
+        match self {
+            SolveStep::NakedSingle { .. } | SolveStep::HiddenSingle { .. } => &[],
+            SolveStep::LockedCandidates { eliminations, .. }
+            | SolveStep::NakedSubset { eliminations, .. }
+            | SolveStep::HiddenSubset { eliminations, .. }
+            | SolveStep::Fish { eliminations, .. }
+            | SolveStep::XyWing { eliminations, .. } => eliminations,
+        }
+    }
+
+    /// Make the placements and eliminations of this step
+    pub fn apply(&self, cands: &mut Candidates) {
+        for (cell, value) in self.placements() {
+            cands.place(cell, value);
+        }
+        for (cell, value) in self.eliminations() {
+            cands.eliminate(*cell, *value);
+        }
+    }
+}
+
+fn join<T>(items: &[T], name: impl Fn(&T) -> String) -> String {
+    items.iter().map(name).collect::<Vec<_>>().join(", ")
+}
+
+impl SolveStep {
+    /// The step as text, naming cells, houses and digits as laid out by `geometry`
+    pub fn describe(&self, geometry: &Geometry) -> String {
+        let cell_name = |c: &usize| geometry.cell_name(*c);
+        let house_name = |h: &usize| geometry.house_name(*h);
+        let symbol = |v: &NonZeroU8| geometry.symbol(*v).to_string();
+        let join_eliminations = |eliminations: &[CellValue]| {
+            join(eliminations, |(cell, value)| {
+                format!("{} from {}", symbol(value), cell_name(cell))
+            })
+        };
+
+        let text = match self {
+            SolveStep::NakedSingle { cell, value } => {
+                format!("{} can only be {}", cell_name(cell), symbol(value))
+            }
+            SolveStep::HiddenSingle { house, cell, value } => format!(
+                "{} can only go in {} within {}",
+                symbol(value),
+                cell_name(cell),
+                house_name(house)
+            ),
+            SolveStep::LockedCandidates {
+                value,
+                house,
+                other,
+                eliminations,
+                ..
+            } => format!(
+                "in {}, {} is confined to {}, so remove {}",
+                house_name(house),
+                symbol(value),
+                house_name(other),
+                join_eliminations(eliminations)
+            ),
+            SolveStep::NakedSubset {
+                house,
+                cells,
+                values,
+                eliminations,
+            } => format!(
+                "{} in {} only hold {}, so remove {}",
+                join(cells, cell_name),
+                house_name(house),
+                join(values, symbol),
+                join_eliminations(eliminations)
+            ),
+            SolveStep::HiddenSubset {
+                house,
+                cells,
+                values,
+                eliminations,
+            } => format!(
+                "{} only fit in {} within {}, so remove {}",
+                join(values, symbol),
+                join(cells, cell_name),
+                house_name(house),
+                join_eliminations(eliminations)
+            ),
+            SolveStep::Fish {
+                value,
+                base,
+                cover,
+                eliminations,
+                ..
+            } => format!(
+                "in {}, {} is confined to {}, so remove {}",
+                join(base, house_name),
+                symbol(value),
+                join(cover, house_name),
+                join_eliminations(eliminations)
+            ),
+            SolveStep::XyWing {
+                pivot,
+                pincers,
+                value,
+                eliminations,
+            } => format!(
+                "{} forces {} or {} to be {}, so remove {}",
+                cell_name(pivot),
+                cell_name(&pincers[0]),
+                cell_name(&pincers[1]),
+                symbol(value),
+                join_eliminations(eliminations)
+            ),
+        };
+        format!("{}: {}", self.technique(), text)
+    }
+}
+
+/// The steps logic took on a board, and where it ended up
+#[derive(Debug, Clone)]
+pub struct SolvePath {
+    pub steps: Vec<SolveStep>,
+    /// The board and candidates after the last step; still has empty cells if logic got stuck
+    pub end: Candidates,
+}
+
+impl SolvePath {
+    pub fn is_solved(&self) -> bool {
+        self.end.board().is_solved()
+    }
+
+    /// The partly filled board and its candidates, if logic got stuck before solving it
+    pub fn stuck(&self) -> Option<&Candidates> {
+        if self.is_solved() {
+            None
+        } else {
+            Some(&self.end)
+        }
+    }
+}
+
+/// Solve a board with logic only, recording every step taken.
+/// Stops when the board is filled, or when no technique applies.
+pub fn solve_path(board: Board) -> SolvePath {
+    solve_path_with(board, &Technique::ALL)
+}
+
+/// Like `solve_path`, but only uses the allowed `techniques`, easiest first.
+/// For example, `Technique::SINGLES` checks that a puzzle can be solved with singles alone.
+pub fn solve_path_with(board: Board, techniques: &[Technique]) -> SolvePath {
+    let mut techniques = techniques.to_vec();
+    techniques.sort();
+
+    let mut cands = Candidates::new(board);
+    let mut steps = vec![];
+    // cages and constraints narrow down candidates by themselves, as in `Candidates::propagate`;
+    // a contradiction means the board cannot be solved, so there is no further step to take
+    let mut pruned = cands.prune_variants().is_ok();
+    while pruned && !cands.board().is_filled() {
+        match find_step(&cands, &techniques) {
+            Some(step) => {
+                step.apply(&mut cands);
+                steps.push(step);
+                pruned = cands.prune_variants().is_ok();
+            }
+            None => break,
+        }
+    }
+    SolvePath { steps, end: cands }
+}
+
+/// Find a step using the first of `techniques` that applies
+pub(crate) fn find_step(cands: &Candidates, techniques: &[Technique]) -> Option<SolveStep> {
+    techniques.iter().find_map(|t| match t {
+        Technique::HiddenSingle => hidden_single(cands),
+        Technique::NakedSingle => naked_single(cands),
+        Technique::Pointing => locked_candidates(cands, true),
+        Technique::Claiming => locked_candidates(cands, false),
+        Technique::NakedPair => naked_subset(cands, 2),
+        Technique::NakedTriple => naked_subset(cands, 3),
+        Technique::NakedQuad => naked_subset(cands, 4),
+        Technique::HiddenPair => hidden_subset(cands, 2),
+        Technique::HiddenTriple => hidden_subset(cands, 3),
+        Technique::HiddenQuad => hidden_subset(cands, 4),
+        Technique::XWing => fish(cands, 2),
+        Technique::Swordfish => fish(cands, 3),
+        Technique::XyWing => xy_wing(cands),
+    })
+}
+
+/// Candidates of a cell, low to high
+fn sorted(cands: &Candidates, cell: usize) -> Vec<NonZeroU8> {
+    let mut values: Vec<_> = cands.get(cell).iter().cloned().collect();
+    values.sort();
+    values
+}
+
+/// Cells of `house` where `value` is a candidate
+fn spots(cands: &Candidates, house: usize, value: NonZeroU8) -> Vec<usize> {
+    cands
+        .board()
+        .geometry()
+        .house(house)
+        .iter()
+        .cloned()
+        .filter(|i| cands.get(*i).contains(&value))
+        .collect()
+}
+
+fn is_placed(cands: &Candidates, house: usize, value: NonZeroU8) -> bool {
+    cands
+        .board()
+        .geometry()
+        .house(house)
+        .iter()
+        .any(|i| cands.board().data[*i].value == Some(value))
+}
+
+/// Every way to choose `k` items out of `items`, keeping their order
+fn combinations<T: Copy>(items: &[T], k: usize) -> Vec<Vec<T>> {
+    if k == 0 {
+        return vec![vec![]];
+    }
+    if items.len() < k {
+        return vec![];
+    }
+    let mut with_first: Vec<Vec<T>> = combinations(&items[1..], k - 1)
+        .into_iter()
+        .map(|mut rest| {
+            rest.insert(0, items[0]);
+            rest
+        })
+        .collect();
+    with_first.extend(combinations(&items[1..], k));
+    with_first
+}
+
+fn naked_single(cands: &Candidates) -> Option<SolveStep> {
+    (0..cands.possibles.len())
+        .filter(|i| cands.board().data[*i].value.is_none())
+        .find(|i| cands.get(*i).len() == 1)
+        .map(|cell| SolveStep::NakedSingle {
+            cell,
+            value: *cands.get(cell).iter().next().unwrap(),
+        })
+}
+
+fn hidden_single(cands: &Candidates) -> Option<SolveStep> {
+    let geometry = cands.board().geometry();
+    // boxes first, since those are the easiest to spot
+    let (boxes, lines): (Vec<usize>, Vec<usize>) =
+        (0..geometry.houses().len()).partition(|h| geometry.kind(*h).is_box());
+    boxes.into_iter().chain(lines).find_map(|house| {
+        geometry
+            .digits()
+            .find_map(|value| match spots(cands, house, value).as_slice() {
+                [cell] => Some(SolveStep::HiddenSingle {
+                    house,
+                    cell: *cell,
+                    value,
+                }),
+                _ => None,
+            })
+    })
+}
+
+/// Pointing when `from_box`: a digit confined to one line of a box is removed from the rest of the line.
+/// Claiming otherwise: a digit confined to one box of a line is removed from the rest of the box.
+fn locked_candidates(cands: &Candidates, from_box: bool) -> Option<SolveStep> {
+    let geometry = cands.board().geometry();
+    let (houses, others): (Vec<usize>, Vec<usize>) =
+        (0..geometry.houses().len()).partition(|h| geometry.kind(*h).is_box() == from_box);
+    for house in houses {
+        for value in geometry.digits() {
+            let cells = spots(cands, house, value);
+            if cells.len() < 2 {
+                continue;
+            }
+            for other in others.iter().cloned() {
+                if !cells.iter().all(|i| geometry.house(other).contains(i)) {
+                    continue;
+                }
+                let eliminations: Vec<CellValue> = spots(cands, other, value)
+                    .into_iter()
+                    .filter(|i| !cells.contains(i))
+                    .map(|i| (i, value))
+                    .collect();
+                if !eliminations.is_empty() {
+                    return Some(SolveStep::LockedCandidates {
+                        pointing: from_box,
+                        value,
+                        house,
+                        other,
+                        cells,
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+fn naked_subset(cands: &Candidates, size: usize) -> Option<SolveStep> {
+    for (house, members) in cands.board().geometry().houses().iter().enumerate() {
+        let open: Vec<usize> = members
+            .iter()
+            .cloned()
+            .filter(|i| cands.board().data[*i].value.is_none())
+            .collect();
+        let small: Vec<usize> = open
+            .iter()
+            .cloned()
+            .filter(|i| (2..=size).contains(&cands.get(*i).len()))
+            .collect();
+
+        for cells in combinations(&small, size) {
+            let mut values: Vec<NonZeroU8> = cells.iter().flat_map(|i| sorted(cands, *i)).collect();
+            values.sort();
+            values.dedup();
+            if values.len() != size {
+                continue;
+            }
+            let eliminations: Vec<CellValue> = open
+                .iter()
+                .filter(|i| !cells.contains(i))
+                .flat_map(|i| {
+                    values
+                        .iter()
+                        .filter(move |v| cands.get(*i).contains(v))
+                        .map(move |v| (*i, *v))
+                })
+                .collect();
+            if !eliminations.is_empty() {
+                return Some(SolveStep::NakedSubset {
+                    house,
+                    cells,
+                    values,
+                    eliminations,
+                });
+            }
+        }
+    }
+    None
+}
+
+fn hidden_subset(cands: &Candidates, size: usize) -> Option<SolveStep> {
+    let geometry = cands.board().geometry();
+    for house in 0..geometry.houses().len() {
+        let candidates: Vec<NonZeroU8> = geometry
+            .digits()
+            .filter(|v| !is_placed(cands, house, *v))
+            .filter(|v| (2..=size).contains(&spots(cands, house, *v).len()))
+            .collect();
+
+        for values in combinations(&candidates, size) {
+            let mut cells: Vec<usize> = values
+                .iter()
+                .flat_map(|v| spots(cands, house, *v))
+                .collect();
+            cells.sort_unstable();
+            cells.dedup();
+            if cells.len() != size {
+                continue;
+            }
+            let eliminations: Vec<CellValue> = cells
+                .iter()
+                .flat_map(|i| {
+                    sorted(cands, *i)
+                        .into_iter()
+                        .filter(|v| !values.contains(v))
+                        .map(move |v| (*i, v))
+                })
+                .collect();
+            if !eliminations.is_empty() {
+                return Some(SolveStep::HiddenSubset {
+                    house,
+                    cells,
+                    values,
+                    eliminations,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// X-Wing (`size` 2) and Swordfish (`size` 3), with rows or columns as the base
+fn fish(cands: &Candidates, size: usize) -> Option<SolveStep> {
+    let geometry = cands.board().geometry();
+    for value in geometry.digits() {
+        for (base_kind, cover_kind) in [
+            (HouseKind::Row, HouseKind::Column),
+            (HouseKind::Column, HouseKind::Row),
+        ] {
+            let cover_of = |i: usize| geometry.house_of_kind(i, cover_kind).unwrap();
+            let lines: Vec<usize> = geometry
+                .houses_of_kind(base_kind)
+                .filter(|h| (2..=size).contains(&spots(cands, *h, value).len()))
+                .collect();
+
+            for base in combinations(&lines, size) {
+                let cells: Vec<usize> = base.iter().flat_map(|h| spots(cands, *h, value)).collect();
+                let mut cover: Vec<usize> = cells.iter().map(|i| cover_of(*i)).collect();
+                cover.sort_unstable();
+                cover.dedup();
+                // the lines of overlapping grids can cross, and then one digit may serve two of them
+                if cover.len() != size
+                    || !are_disjoint(geometry, &base)
+                    || !are_disjoint(geometry, &cover)
+                {
+                    continue;
+                }
+                let eliminations: Vec<CellValue> = cover
+                    .iter()
+                    .flat_map(|h| spots(cands, *h, value))
+                    .filter(|i| !cells.contains(i))
+                    .map(|i| (i, value))
+                    .collect();
+                if !eliminations.is_empty() {
+                    return Some(SolveStep::Fish {
+                        value,
+                        base,
+                        cover,
+                        cells,
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Whether no two of `houses` share a cell
+fn are_disjoint(geometry: &Geometry, houses: &[usize]) -> bool {
+    houses.iter().enumerate().all(|(k, a)| {
+        houses[k + 1..].iter().all(|b| {
+            geometry
+                .house(*a)
+                .iter()
+                .all(|i| !geometry.house(*b).contains(i))
+        })
+    })
+}
+
+fn xy_wing(cands: &Candidates) -> Option<SolveStep> {
+    let geometry = cands.board().geometry();
+    let bivalues: Vec<usize> = (0..cands.possibles.len())
+        .filter(|i| cands.get(*i).len() == 2)
+        .collect();
+
+    for pivot in bivalues.iter().cloned() {
+        let xy = sorted(cands, pivot);
+        // pincers holding one pivot digit and a third digit z
+        let pincers: Vec<(usize, NonZeroU8, NonZeroU8)> = bivalues
+            .iter()
+            .cloned()
+            .filter(|i| geometry.sees(pivot, *i))
+            .filter_map(|i| {
+                let ab = sorted(cands, i);
+                let shared: Vec<_> = ab.iter().filter(|v| xy.contains(v)).cloned().collect();
+                match shared.as_slice() {
+                    [s] => Some((i, *s, *ab.iter().find(|v| *v != s).unwrap())),
+                    _ => None,
+                }
+            })
+            .collect();
+
+        for (a, a_shared, z) in pincers.iter() {
+            for (b, b_shared, b_z) in pincers.iter() {
+                if a >= b || a_shared == b_shared || z != b_z {
+                    continue;
+                }
+                let eliminations: Vec<CellValue> = (0..cands.possibles.len())
+                    .filter(|i| i != a && i != b && geometry.sees(*i, *a) && geometry.sees(*i, *b))
+                    .filter(|i| cands.get(*i).contains(z))
+                    .map(|i| (i, *z))
+                    .collect();
+                if !eliminations.is_empty() {
+                    return Some(SolveStep::XyWing {
+                        pivot,
+                        pincers: [*a, *b],
+                        value: *z,
+                        eliminations,
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{parse_board_list, Board},
+        candidates::Candidates,
+        naive::recursive_solve,
+    };
+
+    use super::{solve_path, solve_path_with, SolveStep, Technique};
+
+    fn board_a1() -> Board {
+        Board::from_str(
+            "_,_,4,1,_,_,5,2,7
+            2,1,3,7,_,_,_,_,_
+            _,_,7,6,2,4,_,_,_
+            3,5,_,2,7,_,_,_,_
+            _,_,_,_,3,_,8,7,5
+            _,4,_,_,_,6,_,1,3
+            4,7,2,_,1,_,_,5,_
+            _,3,1,_,6,2,_,_,9
+            9,_,_,_,_,_,1,8,_",
+        )
+        .unwrap()
+    }
+
+    fn board_a3() -> Board {
+        Board::from_str(
+            "_,_,6,_,9,_,_,_,_
+            1,7,_,_,_,3,_,9,_
+            _,_,_,7,_,_,_,_,5
+            _,_,_,5,_,_,6,_,_
+            _,9,_,_,3,_,2,_,_
+            _,_,4,_,_,2,1,_,_
+            _,_,_,9,7,8,_,_,_
+            _,4,_,_,_,5,_,8,_
+            _,_,_,_,_,6,_,_,_",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_path_replays_to_solution() {
+        let path = solve_path(board_a1());
+        assert!(path.is_solved());
+        assert!(path
+            .steps
+            .iter()
+            .all(|s| s.technique() <= Technique::NakedSingle));
+
+        let mut cands = Candidates::new(board_a1());
+        for step in path.steps.iter() {
+            step.apply(&mut cands);
+        }
+        assert_eq!(
+            recursive_solve(board_a1()).solved.unwrap(),
+            cands.into_board()
+        );
+    }
+
+    #[test]
+    fn test_harder_board_needs_more_than_singles() {
+        let path = solve_path(board_a3());
+        assert!(path.is_solved());
+        assert!(path
+            .steps
+            .iter()
+            .any(|s| s.technique() > Technique::NakedSingle));
+        assert_eq!(
+            recursive_solve(board_a3()).solved.unwrap(),
+            *path.end.board()
+        );
+    }
+
+    #[test]
+    fn test_stuck_with_singles_only() {
+        let path = solve_path_with(board_a3(), &Technique::SINGLES);
+        assert!(!path.is_solved());
+        assert!(path
+            .steps
+            .iter()
+            .all(|s| s.technique() <= Technique::NakedSingle));
+
+        let stuck = path.stuck().unwrap();
+        assert!(!stuck.board().is_filled());
+        assert!((0..81)
+            .filter(|i| stuck.board().data[*i].value.is_none())
+            .all(|i| stuck.get(i).len() >= 2));
+
+        // picking up from where singles got stuck with every technique finishes the puzzle
+        assert!(solve_path(stuck.board().clone()).is_solved());
+    }
+
+    #[test]
+    fn test_path_on_other_sizes() {
+        let path = solve_path(
+            Board::from_str(
+                "1,_,_,4
+                _,4,1,_
+                2,_,_,3
+                _,3,2,_",
+            )
+            .unwrap(),
+        );
+        assert!(path.is_solved());
+
+        let board = Board::from_str(
+            "_,2,3,4,5,_,7,_,9,A,B,C,_,E,_,G
+            5,_,7,_,9,A,B,C,_,E,_,G,1,2,3,_
+            9,A,B,C,_,E,_,G,1,2,3,_,5,_,7,8
+            _,E,_,G,1,2,3,_,5,_,7,8,9,A,_,C
+            2,3,4,_,6,_,8,9,A,B,_,D,_,F,G,1
+            6,_,8,9,A,B,_,D,_,F,G,1,2,_,4,_
+            A,B,_,D,_,F,G,1,2,_,4,_,6,7,8,9
+            _,F,G,1,2,_,4,_,6,7,8,9,_,B,_,D
+            3,_,5,_,7,8,9,A,_,C,_,E,F,G,1,_
+            7,8,9,A,_,C,_,E,F,G,1,_,3,_,5,6
+            _,C,_,E,F,G,1,_,3,_,5,6,7,8,_,A
+            F,G,1,_,3,_,5,6,7,8,_,A,_,C,D,E
+            4,_,6,7,8,9,_,B,_,D,E,F,G,_,2,_
+            8,9,_,B,_,D,E,F,G,_,2,_,4,5,6,7
+            _,D,E,F,G,_,2,_,4,5,6,7,_,9,_,B
+            G,_,2,_,4,5,6,7,_,9,_,B,C,D,E,_",
+        )
+        .unwrap();
+        let path = solve_path(board.clone());
+        assert!(path.is_solved());
+        assert_eq!(
+            "Hidden single: 1 can only go in r1c1 within box 1",
+            path.steps[0].describe(board.geometry())
+        );
+        assert!(path
+            .end
+            .board()
+            .to_string()
+            .starts_with("1,2,3,4,5,6,7,8,9,A,B,C,D,E,F,G,\n"));
+    }
+
+    #[test]
+    fn test_naked_pair_step() {
+        // r1c8 and r1c9 can only be 8 or 9, so no other cell of row 1 can hold them
+        let cands = Candidates::new(
+            Board::from_str(
+                "_,_,_,_,_,_,_,_,_
+                _,_,_,_,_,_,1,2,3
+                _,_,_,_,_,_,4,5,6
+                _,_,_,_,_,_,_,7,_
+                _,_,_,_,_,_,_,_,_
+                _,_,_,_,_,_,_,_,_
+                _,_,_,_,_,_,_,_,7
+                _,_,_,_,_,_,_,_,_
+                _,_,_,_,_,_,_,_,_",
+            )
+            .unwrap(),
+        );
+        let step = super::naked_subset(&cands, 2).unwrap();
+        match &step {
+            SolveStep::NakedSubset { house, cells, .. } => {
+                assert_eq!(0, *house);
+                assert_eq!(vec![7, 8], *cells);
+            }
+            _ => panic!("expected a naked subset, got {:?}", step),
+        }
+        assert_eq!(Technique::NakedPair, step.technique());
+    }
+
+    #[test]
+    fn test_solve_path_prunes_cages() {
+        // singles alone solve this only when the cage sums narrow down candidates after each step
+        let killer = "K2,Rows,unsolved,killer
+            _,_,_,_,_,_,_,_,_
+            _,_,_,_,_,_,_,6,_
+            _,8,_,_,2,4,_,_,1
+            _,_,8,2,_,_,_,_,_
+            1,_,6,_,3,_,_,_,_
+            7,_,_,_,_,_,_,_,3
+            4,7,_,9,_,8,_,5,_
+            8,_,1,5,_,2,_,_,9
+            _,6,_,_,4,_,_,_,_
+            15: r1c1 r1c2
+            13: r1c3 r1c4 r1c5
+            8: r1c6 r1c7
+            9: r1c8 r1c9
+            3: r2c1 r2c2
+            10: r2c3 r2c4
+            18: r2c5 r2c6 r2c7
+            14: r2c8 r2c9
+            20: r3c1 r3c2 r3c3
+            12: r3c4 r3c5 r3c6
+            13: r3c7 r3c8 r3c9
+            8: r4c1 r4c2
+            17: r4c3 r4c4 r4c5
+            7: r4c6 r4c7
+            13: r4c8 r4c9
+            9: r5c1 r5c2 r5c3
+            16: r5c4 r5c5 r5c6
+            20: r5c7 r5c8 r5c9
+            20: r6c1 r6c2 r6c3
+            19: r6c4 r6c5 r6c6
+            6: r6c7 r6c8 r6c9
+            11: r7c1 r7c2
+            11: r7c3 r7c4
+            9: r7c5 r7c6
+            14: r7c7 r7c8 r7c9
+            11: r8c1 r8c2
+            12: r8c3 r8c4 r8c5
+            9: r8c6 r8c7
+            13: r8c8 r8c9
+            15: r9c1 r9c2
+            8: r9c3 r9c4
+            11: r9c5 r9c6
+            11: r9c7 r9c8 r9c9";
+        let board = parse_board_list(&mut killer.as_bytes())
+            .unwrap()
+            .remove(0)
+            .board;
+        let path = solve_path_with(board, &Technique::SINGLES);
+        assert!(path.end.board().is_solved());
+    }
+}
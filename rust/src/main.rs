@@ -1,10 +1,23 @@
-use std::{fs::File, io, time::Instant};
+use std::{env, fs::File, io, rc::Rc, time::Instant};
 
-use rust::{board::parse_board_list, smart::smart_solve};
+use rust::{
+    board::{parse_board_list, AnnotatedBoard},
+    heuristics::{
+        AnyOrder, CellSelector, FirstEmpty, LeastConstrainingValue, MinimumRemainingValues,
+        MostConstrainingCell, ValueOrder,
+    },
+    naive::SolveOptions,
+    smart::{smart_solve, smart_solve_with},
+};
 
 fn main() -> io::Result<()> {
     let boards = parse_board_list(&mut File::open("unsolved.txt")?).expect("error parsing boards");
 
+    if env::args().any(|arg| arg == "--compare-heuristics") {
+        compare_heuristics(boards);
+        return Ok(());
+    }
+
     for board in boards.into_iter() {
         let start = Instant::now();
         let res = smart_solve(board.board);
@@ -19,3 +32,36 @@ fn main() -> io::Result<()> {
 
     Ok(())
 }
+
+/// Solve every board with each pair of cell selector and value order
+fn compare_heuristics(boards: Vec<AnnotatedBoard>) {
+    let cell_selectors: Vec<Rc<dyn CellSelector>> = vec![
+        Rc::new(FirstEmpty),
+        Rc::new(MinimumRemainingValues),
+        Rc::new(MostConstrainingCell),
+    ];
+    let value_orders: Vec<Rc<dyn ValueOrder>> =
+        vec![Rc::new(AnyOrder), Rc::new(LeastConstrainingValue)];
+
+    for board in boards.into_iter() {
+        for cell_selector in cell_selectors.iter() {
+            for value_order in value_orders.iter() {
+                let options = SolveOptions {
+                    cell_selector: cell_selector.clone(),
+                    value_order: value_order.clone(),
+                    ..Default::default()
+                };
+                let start = Instant::now();
+                let res = smart_solve_with(board.board.clone(), &options);
+                println!(
+                    "Board {} with {:?}, {:?} took {} backtracks ({:#?} millisec)",
+                    board.id,
+                    cell_selector,
+                    value_order,
+                    res.nbacktracks,
+                    (Instant::now() - start).as_millis()
+                );
+            }
+        }
+    }
+}
@@ -1,32 +1,59 @@
-use std::num::NonZeroU8;
+use std::{num::NonZeroU8, rc::Rc};
 
-use fnv::FnvHashSet;
+use fnv::{FnvHashMap, FnvHashSet};
 
-use crate::board::{positions::NEIGHBORS, Board};
+use crate::{
+    board::Board,
+    heuristics::{AnyOrder, CellSelector, FirstEmpty, ValueOrder},
+};
 
 pub(crate) fn find_possibles(board: &Board, index: usize) -> FnvHashSet<NonZeroU8> {
-    let seen: FnvHashSet<NonZeroU8> = NEIGHBORS[index]
+    let mut possibles = peer_possibles(board, index);
+    for c in board.geometry.constraints_of(index) {
+        let allowed = constraint_possibles(board, index, *c);
+        possibles.retain(|v| allowed.contains(v));
+    }
+    possibles
+}
+
+/// Digits the peers, cage and restrictions of an empty cell leave it
+fn peer_possibles(board: &Board, index: usize) -> FnvHashSet<NonZeroU8> {
+    let seen: FnvHashSet<NonZeroU8> = board
+        .geometry
+        .neighbors(index)
         .iter()
-        .flat_map(|i| board.data[*i as usize].value)
+        .flat_map(|i| board.data[*i].value)
         .collect();
-    let possibles = [
-        NonZeroU8::new(1).unwrap(),
-        NonZeroU8::new(2).unwrap(),
-        NonZeroU8::new(3).unwrap(),
-        NonZeroU8::new(4).unwrap(),
-        NonZeroU8::new(5).unwrap(),
-        NonZeroU8::new(6).unwrap(),
-        NonZeroU8::new(7).unwrap(),
-        NonZeroU8::new(8).unwrap(),
-        NonZeroU8::new(9).unwrap(),
-    ]
-    .iter()
-    .cloned()
-    .collect::<FnvHashSet<NonZeroU8>>();
+    let mut possibles = board.geometry.digits().collect::<FnvHashSet<NonZeroU8>>();
+    if let Some(cage) = board.geometry.cage_of(index) {
+        let open = board.geometry.cages()[cage].open_digits(board);
+        possibles.retain(|v| open & 1 << v.get() != 0);
+    }
+    for restriction in board.geometry.restrictions() {
+        possibles.retain(|v| restriction.allows(board, index, *v));
+    }
 
     &possibles - &seen
 }
 
+/// Digits an empty cell can take under one of its variant constraints, given an index into
+/// `Geometry::constraints`. The cells of the constraint start from what their peers leave them.
+pub(crate) fn constraint_possibles(
+    board: &Board,
+    index: usize,
+    constraint: usize,
+) -> FnvHashSet<NonZeroU8> {
+    let constraint = &board.geometry.constraints()[constraint];
+    let mut possibles = vec![FnvHashSet::default(); board.data.len()];
+    for i in constraint.cells() {
+        if board.data[i].value.is_none() {
+            possibles[i] = peer_possibles(board, i);
+        }
+    }
+    constraint.prune(board, &mut possibles);
+    possibles.swap_remove(index)
+}
+
 impl Board {
     // pub fn naive_solve(&self) -> Option<(Self, u32)> {
     //     let mut nbacktracks = 0;
@@ -78,40 +105,39 @@ impl Board {
 }
 
 pub fn recursive_solve(board: Board) -> SolveState {
+    recursive_solve_with(board, &SolveOptions::default())
+}
+
+fn guided_solve(board: Board, options: &SolveOptions) -> SolveState {
     // println!("Solving board: \n{}", board);
-    if board.is_filled() {
-        if board.is_solved() {
-            SolveState {
-                nbacktracks: 0,
-                solved: Some(board),
-            }
-        } else {
-            SolveState {
-                nbacktracks: 1,
-                solved: None,
+    match options.cell_selector.select(&board) {
+        None => {
+            if board.is_solved() {
+                SolveState {
+                    solved: Some(board),
+                    ..Default::default()
+                }
+            } else {
+                SolveState {
+                    nbacktracks: 1,
+                    ..Default::default()
+                }
             }
         }
-    } else {
-        if let Some((i, _)) = board
-            .data
-            .iter()
-            .enumerate()
-            .skip_while(|(_, tile)| tile.value.is_some())
-            .next()
-        {
+        Some(i) => {
             let possibles = find_possibles(&board, i);
             if possibles.len() == 1 {
                 // we are forced to enter this number on tile
-                let mut b = board.clone();
+                let mut b = board;
                 b.data[i].value = Some(*possibles.iter().next().unwrap());
-                return recursive_solve(b);
+                guided_solve(b, options)
             } else {
                 let mut nbacktracks = 0;
 
-                for p in possibles.iter() {
+                for p in options.value_order.order(&board, i, &possibles) {
                     let mut b = board.clone();
-                    b.data[i].value = Some(*p);
-                    let mut res = recursive_solve(b);
+                    b.data[i].value = Some(p);
+                    let mut res = guided_solve(b, options);
 
                     if res.solved.is_some() {
                         res.nbacktracks += nbacktracks;
@@ -124,32 +150,305 @@ pub fn recursive_solve(board: Board) -> SolveState {
                 }
 
                 // no solution found here, backtrack
-                return SolveState {
+                SolveState {
                     nbacktracks,
-                    solved: None,
-                };
+                    ..Default::default()
+                }
             }
-        } else {
-            unreachable!("An unfilled board should have an empty cell");
         }
     }
 }
 /// Represents a solving state in recursion
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct SolveState {
     /// Total num backtrack
     pub nbacktracks: u32,
+    /// Num branches cut because they contained a learned nogood
+    pub npruned: u32,
+    /// Num bivalue cells tried by probing
+    pub nprobes: u32,
+    /// Num placements made by probing
+    pub nprobe_hits: u32,
     /// The board, if solved
     pub solved: Option<Board>,
 }
 
+/// Options for the backtracking search
+#[derive(Debug, Clone)]
+pub struct SolveOptions {
+    /// When a branch fails, remember the assignments that caused it (a "nogood"),
+    /// and skip any later branch that contains all of them
+    pub learn_nogoods: bool,
+    /// Before searching, try both digits of every bivalue cell and keep what follows from either
+    /// (only used by `smart_solve_with`)
+    pub probe_bivalues: bool,
+    /// Which empty cell to branch on
+    pub cell_selector: Rc<dyn CellSelector>,
+    /// The order to try the digits of that cell in
+    pub value_order: Rc<dyn ValueOrder>,
+}
+
+impl Default for SolveOptions {
+    fn default() -> Self {
+        Self {
+            learn_nogoods: false,
+            probe_bivalues: false,
+            cell_selector: Rc::new(FirstEmpty),
+            value_order: Rc::new(AnyOrder),
+        }
+    }
+}
+
+/// Solve a board with backtracking, configured by `options`
+pub fn recursive_solve_with(board: Board, options: &SolveOptions) -> SolveState {
+    if !options.learn_nogoods {
+        return guided_solve(board, options);
+    }
+
+    let mut search = LearningSearch::new(&board, options);
+    let solved = search.solve(board).ok();
+    SolveState {
+        nbacktracks: search.nbacktracks,
+        npruned: search.npruned,
+        solved,
+        ..Default::default()
+    }
+}
+
+/// Every solution of a board, up to `limit` of them, such as all 576 Latin squares of size 4
+/// from an empty `Geometry::latin(4)` board
+pub fn find_solutions(board: Board, limit: usize) -> Vec<Board> {
+    let mut solutions = vec![];
+    visit_solutions(board, &mut |solved| {
+        solutions.push(solved);
+        solutions.len() < limit
+    });
+    solutions
+}
+
+/// Number of solutions of a board, counting no further than `limit`.
+/// Unlike `find_solutions`, it keeps none of them, so it can count far more.
+pub fn count_solutions(board: Board, limit: usize) -> usize {
+    let mut count = 0;
+    visit_solutions(board, &mut |_| {
+        count += 1;
+        count < limit
+    });
+    count
+}
+
+/// Backtrack through every solution of a board, branching on the cell with fewest candidates,
+/// and hand each to `visit` until it returns `false`. Returns whether the search went on to the end.
+fn visit_solutions(board: Board, visit: &mut dyn FnMut(Board) -> bool) -> bool {
+    let empty = (0..board.data.len())
+        .filter(|i| board.data[*i].value.is_none())
+        .map(|i| (i, find_possibles(&board, i)))
+        .min_by_key(|(_, possibles)| possibles.len());
+    match empty {
+        None => !board.is_solved() || visit(board),
+        Some((i, possibles)) => {
+            let mut values: Vec<NonZeroU8> = possibles.into_iter().collect();
+            values.sort_unstable();
+            values.into_iter().all(|value| {
+                let mut b = board.clone();
+                b.data[i].value = Some(value);
+                visit_solutions(b, visit)
+            })
+        }
+    }
+}
+
+/// Longest nogood worth remembering; longer ones are rarely completed again and only slow down lookups
+const MAX_NOGOOD_LEN: usize = 8;
+
+/// A cell assignment made during search
+type Assignment = (usize, NonZeroU8);
+
+/// A set of assignments that cannot all hold in a solution, sorted by cell.
+/// Givens are never part of a nogood, since they hold on every branch.
+type Nogood = Vec<Assignment>;
+
+/// Backtracking search that learns a nogood from every failed branch
+struct LearningSearch<'a> {
+    options: &'a SolveOptions,
+    givens: Vec<bool>,
+    nogoods: Vec<Nogood>,
+    /// index into `nogoods` for every assignment, so a new assignment only checks the nogoods it can complete
+    watches: FnvHashMap<Assignment, Vec<usize>>,
+    nbacktracks: u32,
+    npruned: u32,
+}
+
+impl<'a> LearningSearch<'a> {
+    fn new(board: &Board, options: &'a SolveOptions) -> Self {
+        Self {
+            options,
+            givens: board.data.iter().map(|tile| tile.value.is_some()).collect(),
+            nogoods: vec![],
+            watches: FnvHashMap::default(),
+            nbacktracks: 0,
+            npruned: 0,
+        }
+    }
+
+    /// Returns the solved board, or the nogood explaining why `board` has no solution
+    fn solve(&mut self, board: Board) -> Result<Board, Nogood> {
+        let i = match self.options.cell_selector.select(&board) {
+            Some(i) => i,
+            None if board.is_solved() => return Ok(board),
+            // a full board can still break a variant constraint, which any digit may share in;
+            // without one, only the givens themselves can conflict, and no assignment is to blame
+            None => return Err(self.assignments(&board)),
+        };
+
+        let possibles = find_possibles(&board, i);
+
+        // a digit ruled out by a neighbour stays ruled out as long as that neighbour holds it
+        let mut conflict: FnvHashSet<Assignment> = board
+            .geometry
+            .digits()
+            .filter(|v| !possibles.contains(v))
+            .flat_map(|v| self.culprit(&board, i, v))
+            .collect();
+        // a digit may also be ruled out by the sum of the cell's cage, by a variant constraint,
+        // which all the digits in them share in, or by the digits next to it under a restriction
+        let mut related: Vec<usize> = board
+            .geometry
+            .cage_of(i)
+            .iter()
+            .flat_map(|c| board.geometry.cages()[*c].cells.clone())
+            .collect();
+        for restriction in board.geometry.restrictions() {
+            related.extend(restriction.partners(&board.geometry, i));
+        }
+        // a constraint prunes from what the peers, cage and restrictions of each of its cells
+        // leave them, so those digits share in it too
+        for c in board.geometry.constraints_of(i) {
+            for j in board.geometry.constraints()[*c].cells() {
+                related.push(j);
+                related.extend(board.geometry.neighbors(j));
+                if let Some(cage) = board.geometry.cage_of(j) {
+                    related.extend(board.geometry.cages()[cage].cells.iter());
+                }
+                for restriction in board.geometry.restrictions() {
+                    related.extend(restriction.partners(&board.geometry, j));
+                }
+            }
+        }
+        conflict.extend(
+            related
+                .into_iter()
+                .filter(|j| !self.givens[*j])
+                .flat_map(|j| board.data[j].value.map(|v| (j, v))),
+        );
+
+        for p in self.options.value_order.order(&board, i, &possibles) {
+            let assignment = (i, p);
+            let mut b = board.clone();
+            b.data[i].value = Some(p);
+
+            let res = match self.violated(&b, &assignment) {
+                Some(nogood) => {
+                    self.npruned += 1;
+                    Err(nogood)
+                }
+                None => self.solve(b),
+            };
+
+            match res {
+                Ok(solved) => return Ok(solved),
+                Err(nogood) => {
+                    if possibles.len() > 1 {
+                        self.nbacktracks += 1;
+                    }
+                    if !nogood.contains(&assignment) {
+                        // failure does not depend on this cell, so trying its other digits is pointless
+                        return Err(nogood);
+                    }
+                    conflict.extend(nogood.into_iter().filter(|a| a != &assignment));
+                }
+            }
+        }
+
+        let mut nogood: Nogood = conflict.into_iter().collect();
+        nogood.sort_unstable();
+        if possibles.len() > 1 && nogood.len() <= MAX_NOGOOD_LEN {
+            self.learn(nogood.clone());
+        }
+        Err(nogood)
+    }
+
+    /// Every assignment made on `board` so far, if it has variant constraints
+    fn assignments(&self, board: &Board) -> Nogood {
+        if board.geometry.constraints().is_empty() {
+            return vec![];
+        }
+        (0..board.data.len())
+            .filter(|j| !self.givens[*j])
+            .flat_map(|j| board.data[j].value.map(|v| (j, v)))
+            .collect()
+    }
+
+    /// The neighbour assignment that keeps `value` out of cell `index`, or `None` if a given does
+    fn culprit(&self, board: &Board, index: usize, value: NonZeroU8) -> Option<Assignment> {
+        let holders: Vec<usize> = board
+            .geometry
+            .neighbors(index)
+            .iter()
+            .cloned()
+            .filter(|i| board.data[*i].value == Some(value))
+            .collect();
+
+        if holders.iter().any(|i| self.givens[*i]) {
+            None
+        } else {
+            holders.first().map(|i| (*i, value))
+        }
+    }
+
+    /// Finds a learned nogood that `board` completes by making `assignment`
+    fn violated(&self, board: &Board, assignment: &Assignment) -> Option<Nogood> {
+        self.watches.get(assignment)?.iter().find_map(|id| {
+            let nogood = &self.nogoods[*id];
+            if nogood
+                .iter()
+                .all(|(cell, value)| board.data[*cell].value == Some(*value))
+            {
+                Some(nogood.clone())
+            } else {
+                None
+            }
+        })
+    }
+
+    fn learn(&mut self, nogood: Nogood) {
+        let id = self.nogoods.len();
+        for assignment in nogood.iter() {
+            self.watches.entry(*assignment).or_default().push(id);
+        }
+        self.nogoods.push(nogood);
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
-    use crate::board::Board;
+    use std::{num::NonZeroU8, sync::Arc};
 
-    use super::recursive_solve;
+    use crate::{
+        board::{
+            cage::Cage,
+            constraint::{Pair, PairRule},
+            geometry::Geometry,
+            parse_board_list, Board,
+        },
+        filledboard::FilledBoard,
+    };
+
+    use super::{
+        count_solutions, find_solutions, recursive_solve, recursive_solve_with, SolveOptions,
+    };
 
     fn board_a1() -> Board {
         Board::from_str(
@@ -267,6 +566,252 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_solve_learning_nogoods() {
+        let options = SolveOptions {
+            learn_nogoods: true,
+            ..Default::default()
+        };
+        // the search order is fixed, so learning must cut exactly these branches
+        for (board, solution, plain_backtracks, backtracks, pruned) in [
+            (board_a1(), solution_a1(), 129, 86, 0),
+            (board_a2(), solution_a2(), 6504, 1542, 118),
+            (board_a3(), solution_a3(), 11502, 2930, 174),
+        ] {
+            let plain = recursive_solve(board.clone());
+            assert_eq!(plain_backtracks, plain.nbacktracks);
+            let learned = recursive_solve_with(board, &options);
+            assert_eq!(solution, learned.solved.unwrap());
+            assert_eq!(backtracks, learned.nbacktracks);
+            assert_eq!(pruned, learned.npruned);
+        }
+    }
+
+    fn board_4x4() -> Board {
+        Board::from_str(
+            "1,_,_,4
+            _,4,1,_
+            2,_,_,3
+            _,3,2,_",
+        )
+        .unwrap()
+    }
+
+    fn board_16x16() -> Board {
+        Board::from_str(
+            "_,2,3,_,5,_,7,_,9,A,_,C,_,E,_,G
+            5,_,7,_,9,A,_,C,_,E,_,G,1,_,3,_
+            9,A,_,C,_,E,_,G,1,_,3,_,5,_,7,8
+            _,E,_,G,1,_,3,_,5,_,7,8,_,A,_,C
+            2,_,4,_,6,_,8,9,_,B,_,D,_,F,G,_
+            6,_,8,9,_,B,_,D,_,F,G,_,2,_,4,_
+            _,B,_,D,_,F,G,_,2,_,4,_,6,7,_,9
+            _,F,G,_,2,_,4,_,6,7,_,9,_,B,_,D
+            3,_,5,_,7,8,_,A,_,C,_,E,F,_,1,_
+            7,8,_,A,_,C,_,E,F,_,1,_,3,_,5,6
+            _,C,_,E,F,_,1,_,3,_,5,6,_,8,_,A
+            F,_,1,_,3,_,5,6,_,8,_,A,_,C,D,_
+            4,_,6,7,_,9,_,B,_,D,E,_,G,_,2,_
+            _,9,_,B,_,D,E,_,G,_,2,_,4,5,_,7
+            _,D,E,_,G,_,2,_,4,5,_,7,_,9,_,B
+            G,_,2,_,4,5,_,7,_,9,_,B,C,_,E,_",
+        )
+        .unwrap()
+    }
+
+    /// 2x3 boxes
+    fn board_6x6() -> Board {
+        Board::from_str(
+            "_,2,3,_,5,_
+            4,_,6,_,2,3
+            2,3,_,5,_,1
+            _,6,_,2,3,_
+            3,_,5,_,1,_
+            6,_,2,3,_,5",
+        )
+        .unwrap()
+    }
+
+    /// 3x4 boxes
+    fn board_12x12() -> Board {
+        Board::from_str(
+            "_,2,3,_,5,_,7,_,9,A,_,C
+            5,_,7,_,9,A,_,C,_,2,_,4
+            9,A,_,C,_,2,_,4,5,_,7,_
+            _,3,_,5,6,_,8,_,A,_,C,1
+            6,_,8,_,A,_,C,1,_,3,_,5
+            A,_,C,1,_,3,_,5,_,7,8,_
+            _,4,_,6,_,8,9,_,B,_,1,_
+            _,8,9,_,B,_,1,_,3,4,_,6
+            B,_,1,_,3,4,_,6,_,8,_,A
+            4,5,_,7,_,9,_,B,C,_,2,_
+            _,9,_,B,C,_,2,_,4,_,6,7
+            C,_,2,_,4,_,6,7,_,9,_,B",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_solve_other_sizes() {
+        let options = SolveOptions {
+            learn_nogoods: true,
+            ..Default::default()
+        };
+        for board in [board_4x4(), board_6x6(), board_12x12(), board_16x16()] {
+            for res in [
+                recursive_solve(board.clone()),
+                recursive_solve_with(board.clone(), &options),
+            ] {
+                let solved = res.solved.unwrap();
+                assert!(solved.is_solved());
+                assert!(board
+                    .data
+                    .iter()
+                    .zip(solved.data.iter())
+                    .all(|(given, tile)| given.value.is_none() || given == tile));
+            }
+        }
+    }
+
+    /// A killer board with two-cell cages along the rows (and down the last column) of
+    /// `killer_solution`, and the eight givens it needs to have no other solution
+    fn board_killer() -> Board {
+        let solution = killer_solution();
+        let mut cages: Vec<Vec<usize>> = (0..36).map(|k| vec![k / 4 * 9 + k % 4 * 2]).collect();
+        cages.extend((0..4).map(|k| vec![k * 18 + 8]));
+        cages
+            .iter_mut()
+            .for_each(|cells| cells.push(cells[0] + if cells[0] % 9 == 8 { 9 } else { 1 }));
+        cages.push(vec![80]);
+        let cages = cages
+            .into_iter()
+            .map(|cells| Cage {
+                sum: cells
+                    .iter()
+                    .map(|i| solution.data[*i].value.unwrap().get() as usize)
+                    .sum(),
+                cells,
+            })
+            .collect();
+
+        let mut board = Board::empty(Arc::new(
+            solution.geometry().clone().with_cages(cages).unwrap(),
+        ));
+        for i in [37, 40, 43, 50, 51, 58, 60, 78] {
+            board.data[i] = solution.data[i].clone();
+        }
+        board
+    }
+
+    fn killer_solution() -> Board {
+        Board::from_str(
+            "2,5,4,3,6,7,1,9,8
+            3,7,6,1,8,9,4,2,5
+            1,8,9,5,4,2,6,7,3
+            5,6,7,4,9,1,3,8,2
+            4,9,1,2,3,8,5,6,7
+            8,2,3,7,5,6,9,1,4
+            7,1,5,9,2,4,8,3,6
+            6,4,2,8,1,3,7,5,9
+            9,3,8,6,7,5,2,4,1",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_solve_killer() {
+        let options = SolveOptions {
+            learn_nogoods: true,
+            ..Default::default()
+        };
+        let board = board_killer();
+        for res in [
+            recursive_solve(board.clone()),
+            recursive_solve_with(board.clone(), &options),
+        ] {
+            let solved = res.solved.unwrap();
+            assert!(solved.is_solved());
+            assert_eq!(killer_solution().data, solved.data);
+        }
+    }
+
+    #[test]
+    fn test_count_solutions() {
+        let latin = |size: usize| Board::empty(Arc::new(Geometry::latin(size)));
+        assert_eq!(12, count_solutions(latin(3), usize::MAX));
+        assert_eq!(576, count_solutions(latin(4), usize::MAX));
+        assert_eq!(100, count_solutions(latin(4), 100));
+        // the boxes keep half of them as sudoku grids
+        let sudoku = Board::empty(Arc::new(Geometry::square(2)));
+        assert_eq!(288, count_solutions(sudoku, usize::MAX));
+
+        let squares = find_solutions(latin(3), 5);
+        assert_eq!(5, squares.len());
+        assert!(squares.iter().all(|square| square.is_solved()));
+        assert_eq!(vec![solution_a1()], find_solutions(board_a1(), 2));
+    }
+
+    #[test]
+    fn test_learning_nogoods_with_constraints() {
+        let options = SolveOptions {
+            learn_nogoods: true,
+            ..Default::default()
+        };
+        let thermo = "T,x,unsolved
+            6,_,_,1,_,_,_,2,7
+            _,_,3,_,_,5,_,_,8
+            _,_,_,_,2,4,_,_,_
+            _,5,_,2,_,_,6,9,_
+            _,2,6,4,3,_,_,7,_
+            _,_,_,8,_,_,_,_,_
+            4,_,2,9,_,_,_,5,_
+            8,3,1,5,_,2,_,_,_
+            9,_,_,_,_,_,_,_,2
+            whisper: r3c7 r3c8
+            white: r1c3 r2c3
+            thermo: r8c6 r8c7
+            thermo: r8c8 r8c7";
+        let mut boards = vec![parse_board_list(&mut thermo.as_bytes()).unwrap()[0]
+            .board
+            .clone()];
+
+        // clues between neighbors of a random grid, with half of its digits given
+        for seed in 0..40 {
+            let solution = FilledBoard::random(seed);
+            let mut geometry = Geometry::standard().with_diagonals();
+            for k in 0..6 {
+                let a = (seed as usize * 13 + k * 29) % 80;
+                let b = if a % 9 == 8 { a + 9 } else { a + 1 };
+                let (va, vb) = (solution.data[a], solution.data[b]);
+                let rule = match k % 3 {
+                    0 if va.abs_diff(vb) == 1 => PairRule::WhiteDot,
+                    1 if va + vb == 10 => PairRule::X,
+                    _ => PairRule::Greater,
+                };
+                let cells = if rule == PairRule::Greater && va < vb {
+                    [b, a]
+                } else {
+                    [a, b]
+                };
+                geometry = geometry
+                    .with_constraint(Arc::new(Pair { rule, cells }))
+                    .unwrap();
+            }
+            let mut board = Board::empty(Arc::new(geometry));
+            for i in (seed as usize % 2..81).step_by(2) {
+                board.data[i].value = NonZeroU8::new(solution.data[i]);
+            }
+            boards.push(board);
+        }
+
+        for board in boards {
+            let plain = recursive_solve(board.clone()).solved;
+            let learned = recursive_solve_with(board, &options).solved;
+            assert_eq!(plain.is_some(), learned.is_some());
+            assert!(learned.is_none_or(|b| b.is_solved()));
+        }
+    }
+
     #[test]
     fn regress_weird_board() {
         let b = Board::from_str(
@@ -0,0 +1,194 @@
+use std::fmt::{self, Display};
+
+use crate::{
+    board::{geometry::Geometry, AnnotatedBoard, Board},
+    logic::{solve_path, SolveStep, Technique},
+};
+
+/// Score given to a puzzle that logic alone cannot finish, above every technique the crate knows
+pub const STUCK_SCORE: f32 = 6.0;
+
+/// Difficulty band of a puzzle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Diabolical,
+    Extreme,
+}
+
+impl Difficulty {
+    /// Band of a score
+    pub fn from_score(score: f32) -> Self {
+        if score <= 2.0 {
+            Difficulty::Easy
+        } else if score <= 2.8 {
+            Difficulty::Medium
+        } else if score <= 4.0 {
+            Difficulty::Hard
+        } else if score < STUCK_SCORE {
+            Difficulty::Diabolical
+        } else {
+            Difficulty::Extreme
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+            Difficulty::Diabolical => "diabolical",
+            Difficulty::Extreme => "extreme",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Technique {
+    /// Weight of a technique, on the scale used by Sudoku Explainer
+    pub fn rating(&self) -> f32 {
+        match self {
+            Technique::HiddenSingle => 1.5,
+            Technique::NakedSingle => 2.3,
+            Technique::Pointing => 2.6,
+            Technique::Claiming => 2.8,
+            Technique::NakedPair => 3.0,
+            Technique::XWing => 3.2,
+            Technique::HiddenPair => 3.4,
+            Technique::NakedTriple => 3.6,
+            Technique::Swordfish => 3.8,
+            Technique::HiddenTriple => 4.0,
+            Technique::XyWing => 4.2,
+            Technique::NakedQuad => 5.0,
+            Technique::HiddenQuad => 5.4,
+        }
+    }
+}
+
+impl SolveStep {
+    /// Weight of this step. Same as its technique, except that a hidden single in a box is easier to spot.
+    pub fn rating(&self, geometry: &Geometry) -> f32 {
+        match self {
+            SolveStep::HiddenSingle { house, .. } if geometry.kind(*house).is_box() => 1.2,
+            _ => self.technique().rating(),
+        }
+    }
+}
+
+/// How hard a puzzle is for a human solver
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rating {
+    /// Weight of the hardest step needed, or `STUCK_SCORE` if logic gets stuck
+    pub score: f32,
+    pub difficulty: Difficulty,
+    /// Technique of the hardest step needed, if any step was needed
+    pub hardest: Option<Technique>,
+    /// Whether logic alone solves the puzzle
+    pub solved: bool,
+}
+
+/// Rate a puzzle by solving it with logic, always taking the easiest step available.
+/// Like Sudoku Explainer, the score is that of the hardest step on the way.
+pub fn rate(board: &Board) -> Rating {
+    let geometry = board.geometry();
+    let path = solve_path(board.clone());
+    let hardest = path
+        .steps
+        .iter()
+        .max_by(|a, b| a.rating(geometry).partial_cmp(&b.rating(geometry)).unwrap());
+
+    let solved = path.is_solved();
+    let score = if solved {
+        hardest.map(|step| step.rating(geometry)).unwrap_or(0.0)
+    } else {
+        STUCK_SCORE
+    };
+
+    Rating {
+        score,
+        difficulty: Difficulty::from_score(score),
+        hardest: hardest.map(|step| step.technique()),
+        solved,
+    }
+}
+
+impl AnnotatedBoard {
+    /// Rate the board, independent of its `src` label
+    pub fn rating(&self) -> Rating {
+        rate(&self.board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::Board, logic::Technique};
+
+    use super::{rate, Difficulty};
+
+    #[test]
+    fn test_rate_easy() {
+        let rating = rate(
+            &Board::from_str(
+                "_,_,4,1,_,_,5,2,7
+                2,1,3,7,_,_,_,_,_
+                _,_,7,6,2,4,_,_,_
+                3,5,_,2,7,_,_,_,_
+                _,_,_,_,3,_,8,7,5
+                _,4,_,_,_,6,_,1,3
+                4,7,2,_,1,_,_,5,_
+                _,3,1,_,6,2,_,_,9
+                9,_,_,_,_,_,1,8,_",
+            )
+            .unwrap(),
+        );
+        assert!(rating.solved);
+        assert_eq!(Difficulty::Easy, rating.difficulty);
+        assert_eq!(Some(Technique::HiddenSingle), rating.hardest);
+    }
+
+    #[test]
+    fn test_rate_hard() {
+        let rating = rate(
+            &Board::from_str(
+                "_,_,6,_,9,_,_,_,_
+                1,7,_,_,_,3,_,9,_
+                _,_,_,7,_,_,_,_,5
+                _,_,_,5,_,_,6,_,_
+                _,9,_,_,3,_,2,_,_
+                _,_,4,_,_,2,1,_,_
+                _,_,_,9,7,8,_,_,_
+                _,4,_,_,_,5,_,8,_
+                _,_,_,_,_,6,_,_,_",
+            )
+            .unwrap(),
+        );
+        assert!(rating.solved);
+        assert_eq!(Difficulty::Hard, rating.difficulty);
+        assert_eq!(Some(Technique::HiddenTriple), rating.hardest);
+        assert_eq!(4.0, rating.score);
+    }
+
+    #[test]
+    fn test_rate_stuck() {
+        let rating = rate(
+            &Board::from_str(
+                "8,_,_,_,_,_,_,_,_
+                _,_,3,6,_,_,_,_,_
+                _,7,_,_,9,_,2,_,_
+                _,5,_,_,_,7,_,_,_
+                _,_,_,_,4,5,7,_,_
+                _,_,_,1,_,_,_,3,_
+                _,_,1,_,_,_,_,6,8
+                _,_,8,5,_,_,_,1,_
+                _,9,_,_,_,_,4,_,_",
+            )
+            .unwrap(),
+        );
+        assert!(!rating.solved);
+        assert_eq!(Difficulty::Extreme, rating.difficulty);
+    }
+}
@@ -1,11 +1,12 @@
-use crate::naive::{find_possibles, recursive_solve};
+use crate::naive::{find_possibles, recursive_solve, recursive_solve_with};
 use crate::{
-    board::{positions::N_CELLS, Board},
-    naive::SolveState,
+    board::Board,
+    candidates::{Candidates, Contradiction},
+    naive::{SolveOptions, SolveState},
 };
 
 fn fill_naked_singles(mut board: Board) -> Board {
-    for index in 0..N_CELLS {
+    for index in 0..board.data.len() {
         let possibles = find_possibles(&board, index);
         if possibles.len() == 1 {
             board.data[index].value = Some(*possibles.iter().next().unwrap());
@@ -14,7 +15,118 @@ fn fill_naked_singles(mut board: Board) -> Board {
     board
 }
 
+/// Try both digits of every bivalue cell and propagate each one.
+/// A digit that leads to a contradiction is ruled out, and a placement both digits lead to is made.
+/// Returns the number of placements made.
+fn probe_bivalues(cands: &mut Candidates, state: &mut SolveState) -> Result<u32, Contradiction> {
+    let mut nplaced = 0;
+    for i in 0..cands.possibles.len() {
+        if cands.get(i).len() != 2 {
+            continue;
+        }
+        let mut pair: Vec<_> = cands.get(i).iter().cloned().collect();
+        pair.sort();
+        state.nprobes += 1;
+
+        let outcomes: Vec<_> = pair
+            .iter()
+            .map(|value| {
+                let mut trial = cands.clone();
+                trial.place(i, *value);
+                trial.propagate().map(|_| trial)
+            })
+            .collect();
+
+        match (&outcomes[0], &outcomes[1]) {
+            (Err(e), Err(_)) => return Err(*e),
+            (Err(_), Ok(_)) => {
+                cands.place(i, pair[1]);
+                nplaced += 1;
+            }
+            (Ok(_), Err(_)) => {
+                cands.place(i, pair[0]);
+                nplaced += 1;
+            }
+            (Ok(a), Ok(b)) => {
+                for j in 0..a.possibles.len() {
+                    if let Some(value) = a.board().data[j].value {
+                        if cands.board().data[j].value.is_none()
+                            && Some(value) == b.board().data[j].value
+                        {
+                            cands.place(j, value);
+                            nplaced += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    state.nprobe_hits += nplaced;
+    Ok(nplaced)
+}
+
 pub fn smart_solve(board: Board) -> SolveState {
     let fb = fill_naked_singles(board);
     recursive_solve(fb)
 }
+
+pub fn smart_solve_with(board: Board, options: &SolveOptions) -> SolveState {
+    if !options.probe_bivalues {
+        let fb = fill_naked_singles(board);
+        return recursive_solve_with(fb, options);
+    }
+
+    let mut cands = Candidates::new(board);
+    let mut stats = SolveState::default();
+    loop {
+        if cands.propagate().is_err() {
+            return stats;
+        }
+        match probe_bivalues(&mut cands, &mut stats) {
+            Err(_) => return stats,
+            Ok(0) => break,
+            Ok(_) => {}
+        }
+    }
+
+    SolveState {
+        nprobes: stats.nprobes,
+        nprobe_hits: stats.nprobe_hits,
+        ..recursive_solve_with(cands.into_board(), options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{board::Board, naive::SolveOptions};
+
+    use super::{smart_solve, smart_solve_with};
+
+    #[test]
+    fn test_probing_cuts_backtracks() {
+        let board = Board::from_str(
+            "_,_,6,_,9,_,_,_,_
+            1,7,_,_,_,3,_,9,_
+            _,_,_,7,_,_,_,_,5
+            _,_,_,5,_,_,6,_,_
+            _,9,_,_,3,_,2,_,_
+            _,_,4,_,_,2,1,_,_
+            _,_,_,9,7,8,_,_,_
+            _,4,_,_,_,5,_,8,_
+            _,_,_,_,_,6,_,_,_",
+        )
+        .unwrap();
+        let plain = smart_solve(board.clone());
+        let probed = smart_solve_with(
+            board,
+            &SolveOptions {
+                probe_bivalues: true,
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(plain.solved, probed.solved);
+        assert!(probed.nprobes > 0);
+        assert!(probed.nbacktracks < plain.nbacktracks);
+    }
+}
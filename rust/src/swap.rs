@@ -1,22 +1,33 @@
 use std::collections::HashMap;
 
-use crate::board::positions::{HOUSES, House};
+use crate::board::positions::{DIAGONALS, HOUSES, House};
 
 use super::filledboard::FilledBoard;
 
 impl FilledBoard {
     pub fn check_swap(&self) -> (usize, usize) {
-        let bad_rules: Vec<_> = HOUSES
+        self.check_swap_with(&HOUSES)
+    }
+
+    /// Like `check_swap`, for a Sudoku-X board, whose diagonals must hold every digit too
+    pub fn check_swap_x(&self) -> (usize, usize) {
+        let houses: Vec<House> = HOUSES.iter().chain(DIAGONALS.iter()).cloned().collect();
+        self.check_swap_with(&houses)
+    }
+
+    /// Find the two cells that were swapped on a solved board, checking the rules in `houses`
+    pub fn check_swap_with(&self, houses: &[House]) -> (usize, usize) {
+        let bad_rules: Vec<_> = houses
             .iter()
             .filter(|rule| !self.is_valid_on_rule(rule))
             .collect();
         match bad_rules.len() {
-            6 | 4 => {
+            // a swapped cell on a diagonal breaks it too, so Sudoku-X boards can have up to 8
+            4..=8 => {
                 // find the most common indeces in the list of invalid rules, which are the culprit
                 let indicies: Vec<_> = bad_rules
                     .iter()
-                    .map(|rule| self.get_conflict(rule))
-                    .flatten()
+                    .flat_map(|rule| self.get_conflict(rule))
                     .collect();
 
                 let mut freq = HashMap::new();
@@ -30,23 +41,38 @@ impl FilledBoard {
                 // pop the last two and return indices (most common two)
                 let mut ans = [*freq_vec.pop().unwrap().0, *freq_vec.pop().unwrap().0];
                 ans.sort();
-                return (ans[0], ans[1]);
+                if self.swap_pair(&ans[0], &ans[1]).is_solved_with(houses) {
+                    return (ans[0], ans[1]);
+                }
+
+                // diagonals can tie the counts, so fall back to trying every conflicting pair
+                let mut cells: Vec<_> = freq.keys().cloned().collect();
+                cells.sort_unstable();
+                for (k, i0) in cells.iter().enumerate() {
+                    for i1 in &cells[k + 1..] {
+                        if self.swap_pair(i0, i1).is_solved_with(houses) {
+                            return (*i0, *i1);
+                        }
+                    }
+                }
+
+                panic!("No valid solution!");
             }
-            2 => {
+            2 | 3 => {
                 let indicies: Vec<_> = bad_rules
                     .iter()
                     .map(|rule| self.get_conflict(rule))
                     .collect();
 
-                assert_eq!(2, indicies.len());
-
+                // the swapped cells share a row or column and a box, so each breaks the other line,
+                // and at most one of them breaks a diagonal
                 let pair0 = &indicies[0];
                 let pair1 = &indicies[1];
 
                 for i0 in pair0 {
                     for i1 in pair1 {
                         // swap indices in board and check validity
-                        if self.swap_pair(i0, i1).is_solved() {
+                        if self.swap_pair(i0, i1).is_solved_with(houses) {
                             let mut ans = [*i0, *i1];
                             ans.sort();
                             return (ans[0], ans[1]);
@@ -57,7 +83,7 @@ impl FilledBoard {
                 panic!("No valid solution!");
             }
             _ => {
-                panic!("Swapped board should only have 2, 4, or 6 conflicting values");
+                panic!("Swapped board should only have 2 to 8 conflicting values");
             }
         }
     }
@@ -78,7 +104,7 @@ impl FilledBoard {
 
     fn swap_pair(&self, i0: &usize, i1: &usize) -> FilledBoard {
         let mut board = self.clone();
-        let (i0, i1) = (i0.clone(), i1.clone());
+        let (i0, i1) = (*i0, *i1);
         let (v0, v1) = (board.data[i0], board.data[i1]);
         board.data[i0] = v1;
         board.data[i1] = v0;
@@ -120,6 +146,22 @@ mod tests {
         ],
     };
 
+    // a solution that also holds every digit on both diagonals
+    const SOLVED_X: FilledBoard = FilledBoard {
+        #[rustfmt::skip]
+        data: [
+            2,5,4,3,6,7,1,9,8,
+            3,7,6,1,8,9,4,2,5,
+            1,8,9,5,4,2,6,7,3,
+            5,6,7,4,9,1,3,8,2,
+            4,9,1,2,3,8,5,6,7,
+            8,2,3,7,5,6,9,1,4,
+            7,1,5,9,2,4,8,3,6,
+            6,4,2,8,1,3,7,5,9,
+            9,3,8,6,7,5,2,4,1,
+        ],
+    };
+
     #[test]
     fn test_swap_pair() {
         let swapped_b0_0_17 = FilledBoard {
@@ -144,4 +186,27 @@ mod tests {
         assert_eq!((35, 44), SWAPPED_BOARD1.check_swap());
         assert_eq!((2, 3), SWAPPED_BOARD0.check_swap());
     }
+
+    #[test]
+    fn test_solution_random() {
+        // in the same box and row, the same box only, and apart
+        for (seed, &(i0, i1)) in [(0, 1), (0, 10), (12, 70)]
+            .iter()
+            .cycle()
+            .take(15)
+            .enumerate()
+        {
+            let board = FilledBoard::random(seed as u64);
+            if board.data[i0] != board.data[i1] {
+                assert_eq!((i0, i1), board.swap_pair(&i0, &i1).check_swap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_solution_x() {
+        for &(i0, i1) in &[(0, 1), (30, 32), (10, 64), (8, 80)] {
+            assert_eq!((i0, i1), SOLVED_X.swap_pair(&i0, &i1).check_swap_x());
+        }
+    }
 }
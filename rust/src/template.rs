@@ -0,0 +1,411 @@
+use std::num::NonZeroU8;
+
+use crate::{
+    board::{
+        geometry::{Geometry, HouseKind},
+        Board,
+    },
+    naive::SolveState,
+};
+
+/// Words in a `Template`, enough for a 25x25 grid
+const WORDS: usize = 10;
+
+/// One valid placement of a single digit (one cell in every house), as a bitmask where bit `i` is the cell at index `i`.
+///
+/// Each digit on a solved board occupies exactly one template, so solving is choosing
+/// one template per digit, such that none overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Template([u64; WORDS]);
+
+impl Template {
+    fn empty() -> Self {
+        Template([0; WORDS])
+    }
+
+    /// The mask of every cell of a grid with `n_cells` cells
+    fn full(n_cells: usize) -> Self {
+        let mut t = Self::empty();
+        for i in 0..n_cells {
+            t.insert(i);
+        }
+        t
+    }
+
+    fn insert(&mut self, cell: usize) {
+        self.0[cell / 64] |= 1 << (cell % 64);
+    }
+
+    pub fn contains(&self, cell: usize) -> bool {
+        self.0[cell / 64] & 1 << (cell % 64) != 0
+    }
+
+    /// Number of cells in the template
+    pub fn count(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Cell indices contained in the template, low to high
+    pub fn cells(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..WORDS * 64).filter(move |i| self.contains(*i))
+    }
+
+    fn union(&self, other: &Template) -> Template {
+        let mut t = *self;
+        t.0.iter_mut()
+            .zip(other.0.iter())
+            .for_each(|(a, b)| *a |= b);
+        t
+    }
+
+    fn intersection(&self, other: &Template) -> Template {
+        let mut t = *self;
+        t.0.iter_mut()
+            .zip(other.0.iter())
+            .for_each(|(a, b)| *a &= b);
+        t
+    }
+
+    fn difference(&self, other: &Template) -> Template {
+        let mut t = *self;
+        t.0.iter_mut()
+            .zip(other.0.iter())
+            .for_each(|(a, b)| *a &= !b);
+        t
+    }
+
+    fn is_disjoint(&self, other: &Template) -> bool {
+        self.0.iter().zip(other.0.iter()).all(|(a, b)| a & b == 0)
+    }
+
+    fn is_superset(&self, other: &Template) -> bool {
+        self.0.iter().zip(other.0.iter()).all(|(a, b)| a & b == *b)
+    }
+}
+
+/// Every valid placement of a single digit on an empty board (46,656 of them on a 9x9 grid)
+pub fn all_templates(geometry: &Geometry) -> Vec<Template> {
+    enumerate_templates(geometry, Template::empty(), Template::empty())
+}
+
+/// Templates that `value` can still take on `board`: they cover every cell holding `value`
+/// and no cell holding another digit. On a killer board they also keep out of cages
+/// whose sum cannot use `value`, and go through cages whose sum needs it.
+pub fn templates_for(board: &Board, value: NonZeroU8) -> Vec<Template> {
+    let (mut must, mut forbidden) = (Template::empty(), Template::empty());
+    for (i, tile) in board.data.iter().enumerate() {
+        match tile.value {
+            Some(v) if v == value => must.insert(i),
+            Some(_) => forbidden.insert(i),
+            None => {
+                let restrictions = board.geometry().restrictions();
+                if !restrictions.iter().all(|r| r.allows(board, i, value)) {
+                    forbidden.insert(i);
+                }
+            }
+        }
+    }
+
+    let mut needed = vec![];
+    for cage in board.geometry().cages() {
+        let completions = cage.completions(board);
+        if completions
+            .iter()
+            .all(|combo| combo & 1 << value.get() == 0)
+        {
+            cage.cells.iter().for_each(|i| forbidden.insert(*i));
+        } else if completions
+            .iter()
+            .all(|combo| combo & 1 << value.get() != 0)
+        {
+            needed.push(&cage.cells);
+        }
+    }
+
+    let mut templates = enumerate_templates(board.geometry(), must, forbidden);
+    templates.retain(|t| {
+        needed
+            .iter()
+            .all(|cells| cells.iter().any(|i| t.contains(*i)))
+    });
+    templates
+}
+
+/// Templates that contain every cell of `must` and none of `forbidden`.
+/// Only the givens cut the enumeration down, so on big grids this needs a well-filled board.
+fn enumerate_templates(geometry: &Geometry, must: Template, forbidden: Template) -> Vec<Template> {
+    let neighbor_masks: Vec<Template> = (0..geometry.n_cells())
+        .map(|i| {
+            let mut mask = Template::empty();
+            geometry.neighbors(i).iter().for_each(|n| mask.insert(*n));
+            mask
+        })
+        .collect();
+    let rows: Vec<&[usize]> = geometry
+        .houses_of_kind(HouseKind::Row)
+        .map(|r| geometry.house(r))
+        .collect();
+
+    let mut templates = vec![];
+    extend_templates(
+        &rows,
+        Template::empty(),
+        forbidden,
+        &must,
+        &neighbor_masks,
+        &mut templates,
+    );
+    // extra houses (beyond rows, columns and boxes) are not implied by the rows being covered
+    templates.retain(|t| {
+        geometry
+            .houses()
+            .iter()
+            .all(|house| house.iter().any(|i| t.contains(*i)))
+    });
+    templates
+}
+
+/// Pick a cell on the first of `rows` and every row after it, avoiding cells in the `blocked` mask,
+/// unless the row already has one
+fn extend_templates(
+    rows: &[&[usize]],
+    template: Template,
+    blocked: Template,
+    must: &Template,
+    neighbor_masks: &[Template],
+    templates: &mut Vec<Template>,
+) {
+    let (row, rest) = match rows.split_first() {
+        Some(split) => split,
+        None => {
+            templates.push(template);
+            return;
+        }
+    };
+    let required = row.iter().find(|i| must.contains(**i));
+    // rows of overlapping grids can cross, so a cell picked for an earlier row may cover this one
+    if let Some(picked) = row.iter().find(|i| template.contains(**i)) {
+        if required.is_none_or(|r| r == picked) {
+            extend_templates(rest, template, blocked, must, neighbor_masks, templates);
+        }
+        return;
+    }
+    for cell in row.iter().cloned() {
+        if blocked.contains(cell) || required.is_some_and(|r| *r != cell) {
+            continue;
+        }
+        let mut next = template;
+        next.insert(cell);
+        extend_templates(
+            rest,
+            next,
+            blocked.union(&neighbor_masks[cell]),
+            must,
+            neighbor_masks,
+            templates,
+        );
+    }
+}
+
+/// Solve a board by choosing one template per digit.
+/// `nbacktracks` counts template choices that had to be undone.
+pub fn template_solve(board: Board) -> SolveState {
+    let per_digit: Vec<Vec<Template>> = board
+        .geometry()
+        .digits()
+        .map(|value| templates_for(&board, value))
+        .collect();
+
+    let fill = |chosen: &[Template]| {
+        let mut b = board.clone();
+        for (value, template) in board.geometry().digits().zip(chosen.iter()) {
+            for i in template.cells() {
+                b.data[i].value = Some(value);
+            }
+        }
+        b
+    };
+    // templates know nothing of cage sums or variant constraints, so a tiling only counts
+    // once the board it makes keeps them
+    let accept = |chosen: &[Template]| fill(chosen).is_solved();
+
+    let mut nbacktracks = 0;
+    let full = Template::full(board.data.len());
+    let solved = search(per_digit, &full, &accept, &mut nbacktracks).map(|chosen| fill(&chosen));
+
+    SolveState {
+        nbacktracks,
+        solved,
+        ..Default::default()
+    }
+}
+
+/// Returns one template per digit, or `None` if the templates left cannot tile the board
+/// in a way that `accept`s
+fn search(
+    mut per_digit: Vec<Vec<Template>>,
+    full: &Template,
+    accept: &dyn Fn(&[Template]) -> bool,
+    nbacktracks: &mut u32,
+) -> Option<Vec<Template>> {
+    if !overlay(&mut per_digit, full) {
+        return None;
+    }
+
+    // branch on the digit with fewest templates left
+    let d = match per_digit
+        .iter()
+        .enumerate()
+        .filter(|(_, ts)| ts.len() > 1)
+        .min_by_key(|(_, ts)| ts.len())
+    {
+        Some((d, _)) => d,
+        None => {
+            let chosen: Vec<Template> = per_digit.iter().map(|ts| ts[0]).collect();
+            return Some(chosen).filter(|chosen| accept(chosen));
+        }
+    };
+
+    for template in per_digit[d].iter() {
+        let trial = per_digit
+            .iter()
+            .enumerate()
+            .map(|(e, ts)| {
+                if e == d {
+                    vec![*template]
+                } else {
+                    ts.iter()
+                        .cloned()
+                        .filter(|t| t.is_disjoint(template))
+                        .collect()
+                }
+            })
+            .collect();
+
+        match search(trial, full, accept, nbacktracks) {
+            Some(chosen) => return Some(chosen),
+            None => *nbacktracks += 1,
+        }
+    }
+    None
+}
+
+/// Deduce as much as possible by overlaying each digit's templates:
+/// cells common to all templates of a digit belong to it, so no other digit can use them,
+/// and a cell only one digit can reach must belong to that digit.
+/// Returns `false` if some digit or cell of the `full` grid is left without options.
+fn overlay(per_digit: &mut [Vec<Template>], full: &Template) -> bool {
+    loop {
+        if per_digit.iter().any(|ts| ts.is_empty()) {
+            return false;
+        }
+
+        let intersections: Vec<Template> = per_digit
+            .iter()
+            .map(|ts| ts.iter().fold(*full, |acc, t| acc.intersection(t)))
+            .collect();
+        let unions: Vec<Template> = per_digit
+            .iter()
+            .map(|ts| ts.iter().fold(Template::empty(), |acc, t| acc.union(t)))
+            .collect();
+
+        let mut changed = false;
+        for d in 0..per_digit.len() {
+            let claimed: Template = intersections
+                .iter()
+                .enumerate()
+                .filter(|(e, _)| *e != d)
+                .fold(Template::empty(), |acc, (_, inter)| acc.union(inter));
+
+            // cells that no other digit can reach
+            let others: Template = unions
+                .iter()
+                .enumerate()
+                .filter(|(e, _)| *e != d)
+                .fold(Template::empty(), |acc, (_, u)| acc.union(u));
+            let only_here: Template = unions[d].difference(&others);
+
+            let before = per_digit[d].len();
+            per_digit[d].retain(|t| t.is_disjoint(&claimed) && t.is_superset(&only_here));
+            changed |= per_digit[d].len() != before;
+        }
+
+        // every cell must be reachable by some digit
+        let covered = unions.iter().fold(Template::empty(), |acc, u| acc.union(u));
+        if covered != *full {
+            return false;
+        }
+
+        if !changed {
+            return true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        board::{geometry::Geometry, Board},
+        naive::recursive_solve,
+    };
+
+    use super::{all_templates, template_solve, templates_for};
+
+    fn board_a3() -> Board {
+        Board::from_str(
+            "_,_,6,_,9,_,_,_,_
+            1,7,_,_,_,3,_,9,_
+            _,_,_,7,_,_,_,_,5
+            _,_,_,5,_,_,6,_,_
+            _,9,_,_,3,_,2,_,_
+            _,_,4,_,_,2,1,_,_
+            _,_,_,9,7,8,_,_,_
+            _,4,_,_,_,5,_,8,_
+            _,_,_,_,_,6,_,_,_",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_count_templates() {
+        let templates = all_templates(&Geometry::standard());
+        assert_eq!(46656, templates.len());
+        assert!(templates.iter().all(|t| t.count() == 9));
+        // 4 cells on the first row, then 2 on each row after it that are not seen yet, then 1
+        assert_eq!(16, all_templates(&Geometry::square(2)).len());
+    }
+
+    #[test]
+    fn test_template_solve_agrees_with_backtracking() {
+        let res = template_solve(board_a3());
+        assert_eq!(recursive_solve(board_a3()).solved, res.solved);
+    }
+
+    #[test]
+    fn test_solution_fits_templates() {
+        let board = board_a3();
+        let solution = recursive_solve(board.clone()).solved.unwrap();
+        for value in board.geometry().digits() {
+            let placed = templates_for(&board, value)
+                .into_iter()
+                .filter(|t| t.cells().all(|i| solution.data[i].value == Some(value)))
+                .count();
+            assert_eq!(1, placed);
+        }
+    }
+
+    #[test]
+    fn test_template_solve_4x4() {
+        let board = Board::from_str(
+            "1,_,_,4
+            _,4,1,_
+            2,_,_,3
+            _,3,2,_",
+        )
+        .unwrap();
+        assert_eq!(
+            recursive_solve(board.clone()).solved,
+            template_solve(board).solved
+        );
+    }
+}